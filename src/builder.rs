@@ -0,0 +1,179 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Graph, NodeArray, NodeIdx, SimpleNode};
+
+/// The key of every node by its name
+pub type NodeNames = HashMap<String, NodeIdx>;
+
+/// Build a [`Graph`] from named nodes and edges between names
+///
+/// Edges may be added before their endpoints; names are only resolved by [`GraphBuilder::build`]
+#[derive(Debug, Clone)]
+pub struct GraphBuilder<D> {
+    nodes: Vec<(String, D)>,
+    edges: Vec<(String, String)>,
+}
+impl<D> Default for GraphBuilder<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<D> GraphBuilder<D> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            edges: vec![],
+        }
+    }
+
+    pub fn add_node(&mut self, key: impl Into<String>, data: D) -> &mut Self {
+        self.nodes.push((key.into(), data));
+        self
+    }
+
+    pub fn add_edge(&mut self, parent: &str, child: &str) -> &mut Self {
+        self.edges.push((parent.into(), child.into()));
+        self
+    }
+
+    /// Return the graph along with the key of every name
+    pub fn build(self) -> Result<(Graph<SimpleNode<D>>, NodeNames), BuildError> {
+        let mut nodes = NodeArray::with_key();
+        let mut names = HashMap::new();
+        for (name, data) in self.nodes {
+            if names.contains_key(&name) {
+                return Err(BuildError::DuplicateNode { name });
+            }
+            names.insert(name, nodes.insert(SimpleNode::new(data)));
+        }
+        for (parent, child) in self.edges {
+            let resolve = |name: &String| {
+                names
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| BuildError::UnknownNode {
+                        name: name.clone(),
+                        parent: parent.clone(),
+                        child: child.clone(),
+                    })
+            };
+            let parent_idx = resolve(&parent)?;
+            let child_idx = resolve(&child)?;
+            nodes.get_mut(parent_idx).unwrap().children.push(child_idx);
+        }
+        Ok((Graph::new(nodes), names))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Two nodes share a name
+    DuplicateNode { name: String },
+    /// The edge from `parent` to `child` refers to `name`, which is not a node
+    UnknownNode {
+        name: String,
+        parent: String,
+        child: String,
+    },
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DuplicateNode { name } => write!(f, "duplicate node `{name}`"),
+            BuildError::UnknownNode {
+                name,
+                parent,
+                child,
+            } => write!(f, "unknown node `{name}` in edge `{parent}` -> `{child}`"),
+        }
+    }
+}
+impl std::error::Error for BuildError {}
+
+/// Build a `(Graph<SimpleNode<_>>, NodeNames)` from `name: data => [children]` entries
+///
+/// Children may refer to nodes declared later; names are looked up by their identifier string
+///
+/// A duplicate or unknown identifier is a compile error:
+///
+/// ```compile_fail
+/// let (graph, names) = graph::graph! { a: 1 => [], a: 2 => [] };
+/// ```
+///
+/// ```compile_fail
+/// let (graph, names) = graph::graph! { a: 1 => [b] };
+/// ```
+#[macro_export]
+macro_rules! graph {
+    ($($name:ident : $data:expr => [$($child:ident),* $(,)?]),* $(,)?) => {{
+        #[allow(non_camel_case_types, dead_code)]
+        enum Names {
+            $($name,)*
+        }
+        $($(let _ = Names::$child;)*)*
+        let mut builder = $crate::GraphBuilder::new();
+        $(builder.add_node(stringify!($name), $data);)*
+        $($(builder.add_edge(stringify!($name), stringify!($child));)*)*
+        builder.build().expect("names are checked at compile time")
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dependency_order;
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_edge("a", "b")
+            .add_node("a", 1)
+            .add_edge("b", "c")
+            .add_node("b", 2)
+            .add_node("c", 3)
+            .add_edge("a", "c");
+        let (graph, names) = builder.build().unwrap();
+        assert_eq!(graph.nodes().len(), 3);
+        let a = graph.nodes().get(names["a"]).unwrap();
+        assert_eq!(a.data, 1);
+        assert_eq!(a.children, [names["b"], names["c"]]);
+        assert_eq!(
+            dependency_order(&graph, &[names["a"]]),
+            [names["c"], names["b"], names["a"]]
+        );
+    }
+
+    #[test]
+    fn test_graph_macro() {
+        let (graph, names) = crate::graph! {
+            a: "payload a" => [b, c],
+            b: "payload b" => [],
+            c: "payload c" => [b],
+        };
+        assert_eq!(graph.nodes().len(), 3);
+        let a = graph.nodes().get(names["a"]).unwrap();
+        assert_eq!(a.data, "payload a");
+        assert_eq!(a.children, [names["b"], names["c"]]);
+        assert_eq!(
+            dependency_order(&graph, &[names["a"]]),
+            [names["b"], names["c"], names["a"]]
+        );
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let mut builder = GraphBuilder::new();
+        builder.add_node("a", ()).add_node("a", ());
+        assert_eq!(
+            builder.build().unwrap_err(),
+            BuildError::DuplicateNode { name: "a".into() }
+        );
+
+        let mut builder = GraphBuilder::new();
+        builder.add_node("a", ()).add_edge("a", "x");
+        let err = builder.build().unwrap_err();
+        assert_eq!(err.to_string(), "unknown node `x` in edge `a` -> `x`");
+    }
+}
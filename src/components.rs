@@ -0,0 +1,301 @@
+use slotmap::SecondaryMap;
+
+use crate::{Graph, Node, NodeArray, NodeIdx};
+
+/// Return the strongly connected components using Tarjan's algorithm
+///
+/// The components are in reverse topological order of the condensation, i.e. a component comes
+/// before every component that has an edge into it
+pub fn strongly_connected_components<T: Node>(graph: &Graph<T>) -> Vec<Vec<NodeIdx>> {
+    let mut index = SecondaryMap::new();
+    let mut lowlink = SecondaryMap::new();
+    let mut on_stack = SecondaryMap::new();
+    let mut next_index = 0;
+    let mut component_stack = vec![];
+    let mut call_stack: Vec<(NodeIdx, usize)> = vec![];
+    let mut components = vec![];
+    for root in graph.nodes().keys() {
+        if index.contains_key(root) {
+            continue;
+        }
+        index.insert(root, next_index);
+        lowlink.insert(root, next_index);
+        next_index += 1;
+        component_stack.push(root);
+        on_stack.insert(root, ());
+        call_stack.push((root, 0));
+        while let Some((node, next_child)) = call_stack.last_mut() {
+            let node = *node;
+            let children = graph.nodes().get(node).unwrap().children();
+            if let Some(&child) = children.get(*next_child) {
+                *next_child += 1;
+                if !index.contains_key(child) {
+                    index.insert(child, next_index);
+                    lowlink.insert(child, next_index);
+                    next_index += 1;
+                    component_stack.push(child);
+                    on_stack.insert(child, ());
+                    call_stack.push((child, 0));
+                } else if on_stack.contains_key(child) {
+                    lowlink[node] = lowlink[node].min(index[child]);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+            if lowlink[node] != index[node] {
+                continue;
+            }
+            let mut component = vec![];
+            loop {
+                let member = component_stack.pop().unwrap();
+                on_stack.remove(member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            components.push(component);
+        }
+    }
+    components
+}
+
+/// A strongly connected component collapsed into a single node by [`condensation`]
+#[derive(Debug, Clone)]
+pub struct SccNode {
+    /// The nodes of the original graph in this component
+    pub members: Vec<NodeIdx>,
+    /// The components this component has edges into, without duplicates
+    pub children: Vec<NodeIdx>,
+}
+impl Node for SccNode {
+    fn children(&self) -> &[NodeIdx] {
+        &self.children
+    }
+}
+
+/// Collapse every strongly connected component into one node
+///
+/// The returned graph is a DAG; the map translates each original node to its component node
+pub fn condensation<T: Node>(graph: &Graph<T>) -> (Graph<SccNode>, SecondaryMap<NodeIdx, NodeIdx>) {
+    let mut nodes = NodeArray::with_key();
+    let mut component_of = SecondaryMap::new();
+    for members in strongly_connected_components(graph) {
+        let component = nodes.insert(SccNode {
+            members,
+            children: vec![],
+        });
+        for &member in &nodes.get(component).unwrap().members {
+            component_of.insert(member, component);
+        }
+    }
+    for (component, scc) in nodes.iter_mut() {
+        let mut seen = SecondaryMap::new();
+        for &member in &scc.members {
+            for &child in graph.nodes().get(member).unwrap().children() {
+                let child = component_of[child];
+                if child == component || seen.contains_key(child) {
+                    continue;
+                }
+                seen.insert(child, ());
+                scc.children.push(child);
+            }
+        }
+    }
+    (Graph::new(nodes), component_of)
+}
+
+/// Return the weakly connected components, i.e. treating every edge as undirected
+///
+/// Nodes without any edge form singleton components
+pub fn connected_components<T: Node>(graph: &Graph<T>) -> Vec<Vec<NodeIdx>> {
+    let mut leaders = weak_leaders(graph);
+    let mut component_of = SecondaryMap::new();
+    let mut components: Vec<Vec<NodeIdx>> = vec![];
+    for node in graph.nodes().keys() {
+        let leader = find(&mut leaders, node);
+        let component = *component_of.entry(leader).unwrap().or_insert_with(|| {
+            components.push(vec![]);
+            components.len() - 1
+        });
+        components[component].push(node);
+    }
+    components
+}
+
+/// Return the number of weakly connected components
+pub fn component_count<T: Node>(graph: &Graph<T>) -> usize {
+    let leaders = weak_leaders(graph);
+    leaders
+        .iter()
+        .filter(|&(node, &leader)| node == leader)
+        .count()
+}
+
+/// Union-find over the undirected edges mapping each node to another node of its component
+fn weak_leaders<T: Node>(graph: &Graph<T>) -> SecondaryMap<NodeIdx, NodeIdx> {
+    let mut leaders = SecondaryMap::new();
+    for node in graph.nodes().keys() {
+        leaders.insert(node, node);
+    }
+    for (parent, node) in graph.nodes() {
+        for &child in node.children() {
+            let a = find(&mut leaders, parent);
+            let b = find(&mut leaders, child);
+            if a != b {
+                leaders[a] = b;
+            }
+        }
+    }
+    leaders
+}
+
+fn find(leaders: &mut SecondaryMap<NodeIdx, NodeIdx>, mut node: NodeIdx) -> NodeIdx {
+    while leaders[node] != node {
+        let grandparent = leaders[leaders[node]];
+        leaders[node] = grandparent;
+        node = grandparent;
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::{tests::build_graph, topological_sort};
+
+    use super::*;
+
+    fn sorted(mut component: Vec<NodeIdx>) -> Vec<NodeIdx> {
+        component.sort();
+        component
+    }
+
+    #[test]
+    fn test_scc() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 0), (2, 3), (4, 4)]);
+        let components: Vec<Vec<NodeIdx>> = strongly_connected_components(&graph)
+            .into_iter()
+            .map(sorted)
+            .collect();
+        assert_eq!(components.len(), 3);
+        let position = |component: &[NodeIdx]| {
+            components
+                .iter()
+                .position(|c| c == &sorted(component.to_vec()))
+                .unwrap()
+        };
+        assert!(position(&[idx[3]]) < position(&[idx[0], idx[1], idx[2]]));
+        // 4 is unrelated to the other components, so only its presence is fixed
+        assert!(components.contains(&vec![idx[4]]));
+    }
+
+    #[test]
+    fn test_scc_nested_cycles() {
+        let arena = Bump::new();
+        // Inner cycle 1 <-> 2 inside outer cycle 0 -> 1 -> 2 -> 3 -> 0, plus tail 3 -> 4
+        let (graph, idx) =
+            build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 1), (2, 3), (3, 0), (3, 4)]);
+        let components: Vec<Vec<NodeIdx>> = strongly_connected_components(&graph)
+            .into_iter()
+            .map(sorted)
+            .collect();
+        assert_eq!(
+            components,
+            [vec![idx[4]], sorted(vec![idx[0], idx[1], idx[2], idx[3]])]
+        );
+    }
+
+    #[test]
+    fn test_scc_long_chain() {
+        let arena = Bump::new();
+        let len = 100_000;
+        let mut edges: Vec<(usize, usize)> = (0..len - 1).map(|i| (i, i + 1)).collect();
+        let (graph, idx) = build_graph(&arena, len, &edges);
+        let components = strongly_connected_components(&graph);
+        assert_eq!(components.len(), len);
+        assert_eq!(components[0], [idx[len - 1]]);
+        assert_eq!(components[len - 1], [idx[0]]);
+
+        edges.push((len - 1, 0));
+        let (graph, _) = build_graph(&arena, len, &edges);
+        let components = strongly_connected_components(&graph);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), len);
+    }
+
+    #[test]
+    fn test_condensation() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(
+            &arena,
+            5,
+            &[(0, 1), (1, 0), (0, 2), (1, 2), (2, 3), (3, 2), (4, 4)],
+        );
+        let (condensed, component_of) = condensation(&graph);
+        assert_eq!(condensed.nodes().len(), 3);
+        assert_eq!(component_of[idx[0]], component_of[idx[1]]);
+        assert_eq!(component_of[idx[2]], component_of[idx[3]]);
+        let top = condensed.nodes().get(component_of[idx[0]]).unwrap();
+        assert_eq!(top.children, [component_of[idx[2]]]);
+        let starts: Vec<NodeIdx> = condensed.nodes().keys().collect();
+        assert!(topological_sort(&condensed, &starts).is_ok());
+    }
+
+    #[test]
+    fn test_condensation_interlinked_cycles() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(
+            &arena,
+            7,
+            &[
+                (0, 1),
+                (1, 0),
+                (0, 2),
+                (1, 3),
+                (2, 3),
+                (3, 4),
+                (4, 2),
+                (4, 5),
+                (5, 6),
+            ],
+        );
+        let (condensed, component_of) = condensation(&graph);
+        assert_eq!(condensed.nodes().len(), 4);
+        let component = |i: usize| condensed.nodes().get(component_of[idx[i]]).unwrap();
+        assert_eq!(
+            sorted(component(0).members.clone()),
+            sorted(vec![idx[0], idx[1]])
+        );
+        assert_eq!(
+            sorted(component(2).members.clone()),
+            sorted(vec![idx[2], idx[3], idx[4]])
+        );
+        assert_eq!(component(0).children, [component_of[idx[2]]]);
+        assert_eq!(component(2).children, [component_of[idx[5]]]);
+        assert_eq!(component(5).children, [component_of[idx[6]]]);
+        assert!(component(6).children.is_empty());
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 8, &[(0, 1), (2, 1), (3, 4), (5, 4), (6, 6)]);
+        assert_eq!(component_count(&graph), 4);
+        let components = connected_components(&graph);
+        assert_eq!(
+            components,
+            [
+                vec![idx[0], idx[1], idx[2]],
+                vec![idx[3], idx[4], idx[5]],
+                vec![idx[6]],
+                vec![idx[7]],
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use slotmap::SecondaryMap;
+
+use crate::{Graph, Node, NodeIdx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    /// On the DFS stack
+    Gray,
+    /// All descendants finished
+    Black,
+}
+
+/// Return a cycle reachable from `starts` if there is any
+///
+/// The cycle starts and ends at the same node, e.g. `[a, b, c, a]` for `a -> b -> c -> a`
+pub fn detect_cycle<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Option<Vec<NodeIdx>> {
+    // Nodes absent from the map are white
+    let mut color = SecondaryMap::new();
+    let mut stack: Vec<(NodeIdx, usize)> = vec![];
+    for &start in starts {
+        if color.contains_key(start) {
+            continue;
+        }
+        color.insert(start, Color::Gray);
+        stack.push((start, 0));
+        while let Some((node, next_child)) = stack.last_mut() {
+            let node = *node;
+            let children = graph.nodes().get(node).unwrap().children();
+            let Some(&child) = children.get(*next_child) else {
+                stack.pop();
+                color.insert(node, Color::Black);
+                continue;
+            };
+            *next_child += 1;
+            match color.get(child) {
+                None => {
+                    color.insert(child, Color::Gray);
+                    stack.push((child, 0));
+                }
+                Some(Color::Gray) => {
+                    let pos = stack.iter().position(|&(n, _)| n == child).unwrap();
+                    let mut cycle: Vec<NodeIdx> = stack[pos..].iter().map(|&(n, _)| n).collect();
+                    cycle.push(child);
+                    return Some(cycle);
+                }
+                Some(Color::Black) => (),
+            }
+        }
+    }
+    None
+}
+
+/// Return a cycle anywhere in the graph if there is any
+///
+/// Unlike [`detect_cycle`], the first node is not repeated at the end, so a self-loop gives a
+/// single node
+pub fn find_cycle<T: Node>(graph: &Graph<T>) -> Option<Vec<NodeIdx>> {
+    let starts: Vec<NodeIdx> = graph.nodes().keys().collect();
+    let mut cycle = detect_cycle(graph, &starts)?;
+    cycle.pop();
+    Some(cycle)
+}
+
+pub fn is_cyclic<T: Node>(graph: &Graph<T>) -> bool {
+    find_cycle(graph).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_detect_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 3), (3, 1), (0, 4)]);
+        let cycle = detect_cycle(&graph, &[idx[0]]).unwrap();
+        assert_eq!(cycle, [idx[1], idx[2], idx[3], idx[1]]);
+    }
+
+    #[test]
+    fn test_detect_cycle_self_loop() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1), (1, 1)]);
+        let cycle = detect_cycle(&graph, &[idx[0]]).unwrap();
+        assert_eq!(cycle, [idx[1], idx[1]]);
+    }
+
+    #[test]
+    fn test_detect_cycle_dag() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(detect_cycle(&graph, &[idx[0]]), None);
+    }
+
+    #[test]
+    fn test_find_cycle_dag() {
+        let arena = Bump::new();
+        let (graph, _) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(find_cycle(&graph), None);
+        assert!(!is_cyclic(&graph));
+    }
+
+    #[test]
+    fn test_find_cycle_self_loop() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1), (1, 1)]);
+        assert_eq!(find_cycle(&graph), Some(vec![idx[1]]));
+        assert!(is_cyclic(&graph));
+    }
+
+    #[test]
+    fn test_find_cycle_buried() {
+        let arena = Bump::new();
+        // The cycle 4 -> 5 -> 6 -> 4 is not reachable from the acyclic part
+        let (graph, idx) =
+            build_graph(&arena, 7, &[(0, 1), (1, 2), (0, 3), (5, 6), (6, 4), (4, 5)]);
+        assert_eq!(find_cycle(&graph), Some(vec![idx[4], idx[5], idx[6]]));
+    }
+}
@@ -0,0 +1,159 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fmt};
+
+use slotmap::SecondaryMap;
+
+use crate::{order_index, reverse_post_order, Graph, Node, NodeIdx};
+
+/// [`fixed_point_limited`] ran out of iterations before the facts settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IterationLimit {
+    /// The number of transfer calls made
+    pub iterations: usize,
+}
+impl fmt::Display for IterationLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no fixed point after {} iterations", self.iterations)
+    }
+}
+impl std::error::Error for IterationLimit {}
+
+/// Compute a fact for every node reachable from `starts` by iterating `transfer` to a fixed point
+///
+/// Facts are seeded with `init`. `transfer` recomputes the fact of a node from the current facts;
+/// whenever it changes, the parents of the node are queued again. Queued nodes are processed in
+/// reverse post-order.
+///
+/// Never returns if `transfer` does not converge; see [`fixed_point_limited`]
+pub fn fixed_point<T: Node, F: PartialEq>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    init: impl Fn(NodeIdx) -> F,
+    transfer: impl FnMut(NodeIdx, &SecondaryMap<NodeIdx, F>) -> F,
+) -> SecondaryMap<NodeIdx, F> {
+    solve(graph, starts, None, init, transfer).unwrap()
+}
+
+/// Same as [`fixed_point`] but give up after `max_iterations` calls to `transfer`
+pub fn fixed_point_limited<T: Node, F: PartialEq>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    max_iterations: usize,
+    init: impl Fn(NodeIdx) -> F,
+    transfer: impl FnMut(NodeIdx, &SecondaryMap<NodeIdx, F>) -> F,
+) -> Result<SecondaryMap<NodeIdx, F>, IterationLimit> {
+    solve(graph, starts, Some(max_iterations), init, transfer)
+}
+
+fn solve<T: Node, F: PartialEq>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    max_iterations: Option<usize>,
+    init: impl Fn(NodeIdx) -> F,
+    mut transfer: impl FnMut(NodeIdx, &SecondaryMap<NodeIdx, F>) -> F,
+) -> Result<SecondaryMap<NodeIdx, F>, IterationLimit> {
+    let order = reverse_post_order(graph, starts);
+    let index = order_index(&order);
+    let mut parents: SecondaryMap<NodeIdx, Vec<NodeIdx>> = SecondaryMap::new();
+    let mut facts = SecondaryMap::new();
+    for &node in &order {
+        facts.insert(node, init(node));
+        for &child in graph.nodes().get(node).unwrap().children() {
+            parents.entry(child).unwrap().or_default().push(node);
+        }
+    }
+
+    let mut queued: SecondaryMap<NodeIdx, ()> = order.iter().map(|&n| (n, ())).collect();
+    let mut worklist: BinaryHeap<Reverse<(usize, NodeIdx)>> =
+        order.iter().map(|&n| Reverse((index[n], n))).collect();
+    let mut iterations = 0;
+    while let Some(Reverse((_, node))) = worklist.pop() {
+        queued.remove(node);
+        if max_iterations.is_some_and(|max| iterations == max) {
+            return Err(IterationLimit { iterations });
+        }
+        iterations += 1;
+        let fact = transfer(node, &facts);
+        if facts[node] == fact {
+            continue;
+        }
+        facts[node] = fact;
+        for &parent in parents.get(node).into_iter().flatten() {
+            if queued.insert(parent, ()).is_none() {
+                worklist.push(Reverse((index[parent], parent)));
+            }
+        }
+    }
+    Ok(facts)
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_point_reachability() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 1), (1, 3), (0, 4)]);
+        let target = idx[3];
+        let can_reach = fixed_point(
+            &graph,
+            &[idx[0]],
+            |node| node == target,
+            |node, facts| {
+                node == target
+                    || graph.nodes()[node]
+                        .children()
+                        .iter()
+                        .any(|&child| facts[child])
+            },
+        );
+        let expected = [true, true, true, true, false];
+        for (i, expected) in expected.into_iter().enumerate() {
+            assert_eq!(can_reach[idx[i]], expected);
+        }
+    }
+
+    /// Count the nodes on the longest path down from `node`
+    fn count<T: Node>(
+        graph: &Graph<T>,
+        node: NodeIdx,
+        facts: &SecondaryMap<NodeIdx, usize>,
+    ) -> usize {
+        let children = graph.nodes()[node].children();
+        1 + children
+            .iter()
+            .map(|&child| facts[child])
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_fixed_point_limited_diverges() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1), (1, 0)]);
+        let err = fixed_point_limited(
+            &graph,
+            &[idx[0]],
+            100,
+            |_| 0,
+            |node, facts| count(&graph, node, facts),
+        )
+        .unwrap_err();
+        assert_eq!(err, IterationLimit { iterations: 100 });
+
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2)]);
+        let depth = fixed_point_limited(
+            &graph,
+            &[idx[0]],
+            100,
+            |_| 0,
+            |node, facts| count(&graph, node, facts),
+        )
+        .unwrap();
+        assert_eq!(depth[idx[0]], 3);
+    }
+}
@@ -0,0 +1,189 @@
+use slotmap::SecondaryMap;
+
+use crate::{Graph, Node, NodeIdx};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Degree {
+    pub in_degree: usize,
+    pub out_degree: usize,
+}
+
+/// Return the degrees of every node
+///
+/// Multi-edges count once per occurrence, e.g. a node listing the same child twice adds two to
+/// both its out-degree and the child's in-degree
+pub fn degrees<T: Node>(graph: &Graph<T>) -> SecondaryMap<NodeIdx, Degree> {
+    let mut degrees = SecondaryMap::new();
+    for (node, value) in graph.nodes() {
+        degrees.insert(
+            node,
+            Degree {
+                in_degree: 0,
+                out_degree: value.children().len(),
+            },
+        );
+    }
+    for (_, value) in graph.nodes() {
+        for &child in value.children() {
+            degrees[child].in_degree += 1;
+        }
+    }
+    degrees
+}
+
+/// Count the edges into `node`, scanning the whole graph
+pub fn in_degree<T: Node>(graph: &Graph<T>, node: NodeIdx) -> usize {
+    graph
+        .nodes()
+        .values()
+        .flat_map(|value| value.children())
+        .filter(|&&child| child == node)
+        .count()
+}
+
+pub fn out_degree<T: Node>(graph: &Graph<T>, node: NodeIdx) -> usize {
+    graph.nodes().get(node).unwrap().children().len()
+}
+
+/// Same as [`Graph::roots`]
+pub fn roots<T: Node>(graph: &Graph<T>) -> Vec<NodeIdx> {
+    graph.roots()
+}
+
+/// Same as [`Graph::leaves`]
+pub fn leaves<T: Node>(graph: &Graph<T>) -> Vec<NodeIdx> {
+    graph.leaves()
+}
+
+impl<T: Node> Graph<T> {
+    /// Return the number of children of every node, zero included
+    pub fn out_degrees(&self) -> SecondaryMap<NodeIdx, usize> {
+        degrees(self)
+            .into_iter()
+            .map(|(node, degree)| (node, degree.out_degree))
+            .collect()
+    }
+
+    /// Return the number of edges into every node, zero included
+    ///
+    /// Multi-edges count once per occurrence and a self-loop counts as both an in- and out-edge
+    pub fn in_degrees(&self) -> SecondaryMap<NodeIdx, usize> {
+        degrees(self)
+            .into_iter()
+            .map(|(node, degree)| (node, degree.in_degree))
+            .collect()
+    }
+
+    /// Return the `(in, out)` degrees of `node`, scanning the whole graph for its in-degree
+    pub fn degree(&self, node: NodeIdx) -> (usize, usize) {
+        (in_degree(self, node), out_degree(self, node))
+    }
+
+    /// Return the nodes no edge points to
+    pub fn roots(&self) -> Vec<NodeIdx> {
+        let mut has_parent = SecondaryMap::new();
+        for node in self.nodes().values() {
+            for &child in node.children() {
+                has_parent.insert(child, ());
+            }
+        }
+        self.nodes()
+            .keys()
+            .filter(|&node| !has_parent.contains_key(node))
+            .collect()
+    }
+
+    /// Return the nodes without children
+    pub fn leaves(&self) -> Vec<NodeIdx> {
+        self.nodes()
+            .iter()
+            .filter(|(_, node)| node.children().is_empty())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_degrees() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (0, 1), (0, 2), (1, 2)]);
+        let degrees = degrees(&graph);
+        assert_eq!(
+            degrees[idx[0]],
+            Degree {
+                in_degree: 0,
+                out_degree: 3
+            }
+        );
+        assert_eq!(
+            degrees[idx[1]],
+            Degree {
+                in_degree: 2,
+                out_degree: 1
+            }
+        );
+        assert_eq!(in_degree(&graph, idx[2]), 2);
+        assert_eq!(out_degree(&graph, idx[2]), 0);
+    }
+
+    #[test]
+    fn test_degree_maps() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 1), (1, 1), (1, 2)]);
+        let in_degrees = graph.in_degrees();
+        let out_degrees = graph.out_degrees();
+        assert_eq!(in_degrees.len(), 4);
+        assert_eq!(out_degrees.len(), 4);
+        let count =
+            |map: &SecondaryMap<NodeIdx, usize>| idx.iter().map(|&i| map[i]).collect::<Vec<_>>();
+        assert_eq!(count(&in_degrees), [0, 3, 1, 0]);
+        assert_eq!(count(&out_degrees), [2, 2, 0, 0]);
+        assert_eq!(graph.degree(idx[1]), (3, 2));
+        assert_eq!(graph.degree(idx[3]), (0, 0));
+    }
+
+    #[test]
+    fn test_edges_match_degrees() {
+        let arena = Bump::new();
+        let edges: Vec<(usize, usize)> = (0..10)
+            .flat_map(|i| [(i, (i * 3) % 10), (i, 9 - i)])
+            .collect();
+        let (graph, _) = build_graph(&arena, 10, &edges);
+        assert_eq!(graph.edges().count(), 20);
+        assert_eq!(graph.edge_count(), 20);
+        assert_eq!(graph.in_degrees().values().sum::<usize>(), 20);
+        let mut from_edges: SecondaryMap<NodeIdx, usize> = SecondaryMap::new();
+        for (_, child) in graph.edges() {
+            *from_edges.entry(child).unwrap().or_default() += 1;
+        }
+        for (node, &count) in &graph.in_degrees() {
+            assert_eq!(from_edges.get(node).copied().unwrap_or(0), count);
+        }
+    }
+
+    #[test]
+    fn test_roots_and_leaves() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 6, &[(0, 2), (1, 2), (2, 3), (1, 4)]);
+        assert_eq!(graph.roots(), [idx[0], idx[1], idx[5]]);
+        assert_eq!(graph.leaves(), [idx[3], idx[4], idx[5]]);
+        assert_eq!(roots(&graph), graph.roots());
+        assert_eq!(leaves(&graph), graph.leaves());
+    }
+
+    #[test]
+    fn test_roots_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 0), (1, 2), (3, 2)]);
+        assert_eq!(graph.roots(), [idx[3]]);
+        assert_eq!(graph.leaves(), [idx[2]]);
+    }
+}
@@ -0,0 +1,453 @@
+use std::{collections::HashMap, io};
+
+use slotmap::SecondaryMap;
+
+use crate::{dense_ids, Graph, Node, NodeIdx};
+
+/// Graphviz attributes of a node; unset fields are left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotNodeAttrs {
+    pub label: Option<String>,
+    pub shape: Option<String>,
+    pub color: Option<String>,
+    pub style: Option<String>,
+}
+
+/// Graphviz attributes of an edge; unset fields are left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotEdgeAttrs {
+    pub label: Option<String>,
+    pub color: Option<String>,
+    pub style: Option<String>,
+}
+
+pub fn to_dot<T: Node>(graph: &Graph<T>) -> String {
+    let mut dot = vec![];
+    write_dot(graph, &mut dot).unwrap();
+    String::from_utf8(dot).unwrap()
+}
+
+/// Same as [`to_dot`] but stream the output into `w` line by line
+///
+/// Every line takes a few small writes, so wrap files and sockets in an [`io::BufWriter`]
+pub fn write_dot<T: Node, W: io::Write>(graph: &Graph<T>, w: &mut W) -> io::Result<()> {
+    write_dot_with(
+        graph,
+        w,
+        |_, _| DotNodeAttrs::default(),
+        |_, _| DotEdgeAttrs::default(),
+    )
+}
+
+/// Direction of the rank layout of a DOT graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankDir {
+    TopBottom,
+    LeftRight,
+    BottomTop,
+    RightLeft,
+}
+impl RankDir {
+    fn as_str(self) -> &'static str {
+        match self {
+            RankDir::TopBottom => "TB",
+            RankDir::LeftRight => "LR",
+            RankDir::BottomTop => "BT",
+            RankDir::RightLeft => "RL",
+        }
+    }
+}
+
+/// Assigns a node to a named cluster subgraph
+pub type ClusterFn<T> = Box<dyn Fn(NodeIdx, &T) -> Option<String>>;
+
+/// Graph-level settings of DOT output
+pub struct DotOptions<T> {
+    pub rankdir: Option<RankDir>,
+    pub graph_name: Option<String>,
+    /// Name of the cluster subgraph a node is drawn in, or `None` for the top level
+    pub cluster: Option<ClusterFn<T>>,
+    /// Name nodes `n0`, `n1`, ... in iteration order instead of by their [`NodeIdx`]
+    ///
+    /// The output then stays the same when a node is removed and another takes its slot
+    pub stable_ids: bool,
+}
+impl<T> Default for DotOptions<T> {
+    fn default() -> Self {
+        Self {
+            rankdir: None,
+            graph_name: None,
+            cluster: None,
+            stable_ids: false,
+        }
+    }
+}
+
+/// Same as [`to_dot`] but with attributes for every node and edge
+///
+/// Attribute values are quoted and escaped, so any string is safe to use as a label
+pub fn to_dot_with<T: Node>(
+    graph: &Graph<T>,
+    node_attrs: impl Fn(NodeIdx, &T) -> DotNodeAttrs,
+    edge_attrs: impl Fn(NodeIdx, NodeIdx) -> DotEdgeAttrs,
+) -> String {
+    let mut dot = vec![];
+    write_dot_with(graph, &mut dot, node_attrs, edge_attrs).unwrap();
+    String::from_utf8(dot).unwrap()
+}
+
+/// Same as [`to_dot_with`] but stream the output into `w` line by line
+pub fn write_dot_with<T: Node, W: io::Write>(
+    graph: &Graph<T>,
+    w: &mut W,
+    node_attrs: impl Fn(NodeIdx, &T) -> DotNodeAttrs,
+    edge_attrs: impl Fn(NodeIdx, NodeIdx) -> DotEdgeAttrs,
+) -> io::Result<()> {
+    render(graph, &DotOptions::default(), node_attrs, edge_attrs, w)
+}
+
+/// Same as [`to_dot`] but with the escaped `label` of every node
+pub fn to_dot_labeled<T: Node>(graph: &Graph<T>, label: impl Fn(NodeIdx, &T) -> String) -> String {
+    to_dot_with(
+        graph,
+        |i, node| DotNodeAttrs {
+            label: Some(label(i, node)),
+            ..Default::default()
+        },
+        |_, _| DotEdgeAttrs::default(),
+    )
+}
+
+/// Same as [`to_dot_with`] but with graph-level `options` and default edge attributes
+///
+/// Clusters are declared in order of their first node, after the top-level nodes and before
+/// any edge
+pub fn to_dot_opts<T: Node>(
+    graph: &Graph<T>,
+    options: &DotOptions<T>,
+    node_attrs: impl Fn(NodeIdx, &T) -> DotNodeAttrs,
+) -> String {
+    let mut dot = vec![];
+    let edge_attrs = |_, _| DotEdgeAttrs::default();
+    render(graph, options, node_attrs, edge_attrs, &mut dot).unwrap();
+    String::from_utf8(dot).unwrap()
+}
+
+fn render<T: Node, W: io::Write>(
+    graph: &Graph<T>,
+    options: &DotOptions<T>,
+    node_attrs: impl Fn(NodeIdx, &T) -> DotNodeAttrs,
+    edge_attrs: impl Fn(NodeIdx, NodeIdx) -> DotEdgeAttrs,
+    w: &mut W,
+) -> io::Result<()> {
+    match &options.graph_name {
+        Some(name) => writeln!(w, "digraph \"{}\" {{", escape(name))?,
+        None => writeln!(w, "digraph {{")?,
+    }
+    if let Some(rankdir) = options.rankdir {
+        writeln!(w, "rankdir={}", rankdir.as_str())?;
+    }
+    let stable_ids: SecondaryMap<NodeIdx, usize> = if options.stable_ids {
+        dense_ids(graph)
+    } else {
+        SecondaryMap::new()
+    };
+    let id = |i: NodeIdx| match stable_ids.get(i) {
+        Some(n) => format!("n{n}"),
+        None => format!("{i:?}"),
+    };
+    let mut cluster_index = HashMap::new();
+    let mut clusters: Vec<(String, Vec<NodeIdx>)> = vec![];
+    for (i, node) in graph.nodes() {
+        let Some(name) = options
+            .cluster
+            .as_ref()
+            .and_then(|cluster| cluster(i, node))
+        else {
+            write_node(w, &id(i), node_attrs(i, node))?;
+            continue;
+        };
+        let index = *cluster_index.entry(name.clone()).or_insert_with(|| {
+            clusters.push((name, vec![]));
+            clusters.len() - 1
+        });
+        clusters[index].1.push(i);
+    }
+    for (name, members) in clusters {
+        let name = escape(&name);
+        writeln!(w, "subgraph \"cluster_{name}\" {{\nlabel=\"{name}\"")?;
+        for i in members {
+            write_node(w, &id(i), node_attrs(i, &graph.nodes()[i]))?;
+        }
+        writeln!(w, "}}")?;
+    }
+    for (i, child) in graph.edges() {
+        let DotEdgeAttrs {
+            label,
+            color,
+            style,
+        } = edge_attrs(i, child);
+        write!(w, "\"{}\" -> \"{}\"", id(i), id(child))?;
+        write_attrs(w, &[("label", label), ("color", color), ("style", style)])?;
+    }
+    write!(w, "}}")
+}
+
+fn write_node(w: &mut impl io::Write, id: &str, attrs: DotNodeAttrs) -> io::Result<()> {
+    let DotNodeAttrs {
+        label,
+        shape,
+        color,
+        style,
+    } = attrs;
+    write!(w, "\"{id}\"")?;
+    write_attrs(
+        w,
+        &[
+            ("label", label),
+            ("shape", shape),
+            ("color", color),
+            ("style", style),
+        ],
+    )
+}
+
+/// Write ` [key="value", ..]` for the set attributes and end the line
+fn write_attrs(w: &mut impl io::Write, attrs: &[(&str, Option<String>)]) -> io::Result<()> {
+    let mut set = attrs
+        .iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+        .peekable();
+    if set.peek().is_some() {
+        let list: Vec<String> = set
+            .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+            .collect();
+        write!(w, " [{}]", list.join(", "))?;
+    }
+    writeln!(w)
+}
+
+/// Escape `s` for use inside a quoted DOT string
+///
+/// A newline becomes the `\n` line break of Graphviz labels
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    /// Read the quoted DOT string at the start of `s` the way Graphviz does, returning its raw
+    /// content and the rest of the input
+    fn parse_quoted(s: &str) -> (String, &str) {
+        let mut chars = s.strip_prefix('"').unwrap().char_indices();
+        let mut content = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return (content, &s[i + 2..]),
+                '\\' => match chars.next().unwrap().1 {
+                    '"' => content.push('"'),
+                    c => {
+                        content.push('\\');
+                        content.push(c);
+                    }
+                },
+                '\n' => panic!("raw newline in quoted string"),
+                c => content.push(c),
+            }
+        }
+        panic!("unterminated string")
+    }
+
+    #[test]
+    fn test_to_dot_isolated_node() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1)]);
+        let dot = to_dot(&graph);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with('}'));
+        assert!(dot.contains(&format!("\"{:?}\"\n", idx[2])));
+        assert!(dot.contains(&format!("\"{:?}\" -> \"{:?}\"\n", idx[0], idx[1])));
+        assert!(!dot.contains(&format!("\"{:?}\" ->", idx[2])));
+    }
+
+    #[test]
+    fn test_to_dot_with_escaping() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1)]);
+        let label = "say \"hi\"\\n\nnext";
+        let dot = to_dot_with(
+            &graph,
+            |i, _| DotNodeAttrs {
+                label: (i == idx[0]).then(|| label.to_string()),
+                shape: Some("box".into()),
+                ..Default::default()
+            },
+            |_, _| DotEdgeAttrs {
+                style: Some("dashed".into()),
+                ..Default::default()
+            },
+        );
+        let start = format!("\"{:?}\" [label=", idx[0]);
+        let line = dot.lines().find(|line| line.starts_with(&start)).unwrap();
+        let (content, rest) = parse_quoted(&line[start.len()..]);
+        assert_eq!(content, "say \"hi\"\\\\n\\nnext");
+        assert_eq!(rest, ", shape=\"box\"]");
+        assert!(dot.contains(&format!("\"{:?}\" [shape=\"box\"]\n", idx[1])));
+        assert!(dot.contains(&format!(
+            "\"{:?}\" -> \"{:?}\" [style=\"dashed\"]\n",
+            idx[0], idx[1]
+        )));
+    }
+
+    #[test]
+    fn test_to_dot_labeled() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1)]);
+        let dot = to_dot_labeled(&graph, |i, _| {
+            if i == idx[0] {
+                r#"C:\dir "quoted""#.into()
+            } else {
+                "plain".into()
+            }
+        });
+        let expected = [
+            "digraph {".to_string(),
+            format!(r#""{:?}" [label="C:\\dir \"quoted\""]"#, idx[0]),
+            format!(r#""{:?}" [label="plain"]"#, idx[1]),
+            format!(r#""{:?}" -> "{:?}""#, idx[0], idx[1]),
+            "}".into(),
+        ];
+        assert_eq!(dot, expected.join("\n"));
+    }
+
+    #[test]
+    fn test_to_dot_opts_clusters() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 3), (3, 4)]);
+        let names = idx.clone();
+        let options = DotOptions {
+            rankdir: Some(RankDir::LeftRight),
+            graph_name: Some("deps".into()),
+            cluster: Some(Box::new(move |i, _| {
+                let n = names.iter().position(|&node| node == i).unwrap();
+                match n {
+                    0 | 2 => Some("even".into()),
+                    1 | 3 => Some("odd".into()),
+                    _ => None,
+                }
+            })),
+            stable_ids: false,
+        };
+        let dot = to_dot_opts(&graph, &options, |i, _| DotNodeAttrs {
+            label: Some(idx.iter().position(|&node| node == i).unwrap().to_string()),
+            ..Default::default()
+        });
+        let id = |i: usize| format!("\"{:?}\"", idx[i]);
+        let expected = [
+            "digraph \"deps\" {".to_string(),
+            "rankdir=LR".into(),
+            format!("{} [label=\"4\"]", id(4)),
+            "subgraph \"cluster_even\" {".into(),
+            "label=\"even\"".into(),
+            format!("{} [label=\"0\"]", id(0)),
+            format!("{} [label=\"2\"]", id(2)),
+            "}".into(),
+            "subgraph \"cluster_odd\" {".into(),
+            "label=\"odd\"".into(),
+            format!("{} [label=\"1\"]", id(1)),
+            format!("{} [label=\"3\"]", id(3)),
+            "}".into(),
+            format!("{} -> {}", id(0), id(1)),
+            format!("{} -> {}", id(1), id(2)),
+            format!("{} -> {}", id(2), id(3)),
+            format!("{} -> {}", id(3), id(4)),
+            "}".into(),
+        ];
+        assert_eq!(dot, expected.join("\n"));
+    }
+
+    #[test]
+    fn test_to_dot_stable_ids() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2)]);
+        let options = DotOptions {
+            stable_ids: true,
+            ..Default::default()
+        };
+        let render = |graph: &Graph<_>| to_dot_opts(graph, &options, |_, _| Default::default());
+        let before = render(&graph);
+        assert_eq!(
+            before,
+            "digraph {\n\"n0\"\n\"n1\"\n\"n2\"\n\"n0\" -> \"n1\"\n\"n1\" -> \"n2\"\n}"
+        );
+
+        let debug_before = to_dot(&graph);
+        let removed = graph.remove_node(idx[1]).unwrap();
+        let reinserted = graph.nodes_mut().insert(removed);
+        assert_ne!(reinserted, idx[1]);
+        graph.add_edge(idx[0], reinserted).unwrap();
+        assert_eq!(render(&graph), before);
+        assert_ne!(to_dot(&graph), debug_before);
+
+        graph.add_edge(idx[0], idx[2]).unwrap();
+        let after = render(&graph);
+        let added: Vec<&str> = after
+            .lines()
+            .filter(|l| !before.lines().any(|b| b == *l))
+            .collect();
+        assert_eq!(added, ["\"n0\" -> \"n2\""]);
+        assert_eq!(after.lines().count(), before.lines().count() + 1);
+    }
+
+    /// Accept `remaining` bytes, then fail every write
+    struct FailAfter {
+        remaining: usize,
+    }
+    impl io::Write for FailAfter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::other("out of space"));
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let arena = Bump::new();
+        let (graph, _) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (3, 3)]);
+        let mut out = vec![];
+        write_dot(&graph, &mut out).unwrap();
+        let dot = to_dot(&graph);
+        assert_eq!(String::from_utf8(out).unwrap(), dot);
+
+        let mut out = FailAfter { remaining: 20 };
+        let err = write_dot(&graph, &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "out of space");
+        let mut out = FailAfter {
+            remaining: dot.len(),
+        };
+        write_dot(&graph, &mut out).unwrap();
+    }
+}
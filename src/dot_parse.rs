@@ -0,0 +1,529 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{Graph, NodeArray, NodeIdx, NodeNames, SimpleNode};
+
+/// Attributes of a node statement parsed by [`from_dot`]
+pub type DotAttrs = BTreeMap<String, String>;
+
+/// DOT input [`from_dot`] cannot read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotParseError {
+    /// 1-based line of the offending token
+    pub line: usize,
+    /// 1-based column of the offending token, in characters
+    pub column: usize,
+    pub message: String,
+}
+impl fmt::Display for DotParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+impl std::error::Error for DotParseError {}
+
+/// Parse a `digraph` written by [`to_dot_with`](crate::to_dot_with) or by hand
+///
+/// Supported are quoted and unquoted ids, node statements with `[key=value, ...]` attributes,
+/// chained `a -> b -> c` edges, graph attributes like `rankdir=LR` and comments. Nodes are
+/// created the first time they appear; the attributes of all their node statements are merged
+/// into the payload. Graph and edge attributes are read but dropped as edges carry no payload
+///
+/// Keywords are case-insensitive. Subgraphs, undirected graphs, `strict` and `node`/`edge`
+/// default statements are rejected.
+/// Within quoted strings `\"`, `\\` and `\n` are decoded; other escapes are kept as written
+pub fn from_dot(s: &str) -> Result<(Graph<SimpleNode<DotAttrs>>, NodeNames), DotParseError> {
+    let tokens = tokenize(s)?;
+    Parser {
+        tokens: &tokens,
+        next: 0,
+        end: end_position(s),
+        nodes: NodeArray::with_key(),
+        names: NodeNames::new(),
+    }
+    .parse()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Id { text: String, quoted: bool },
+    Arrow,
+    Undirected,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+}
+
+impl Token {
+    /// Return the lowercased text of an unquoted id, as DOT keywords are case-insensitive
+    fn keyword(&self) -> Option<String> {
+        match self {
+            Token::Id {
+                text,
+                quoted: false,
+            } => Some(text.to_ascii_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+impl Position {
+    fn error(self, message: impl Into<String>) -> DotParseError {
+        DotParseError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+fn end_position(s: &str) -> Position {
+    let line = s.split('\n').count();
+    let column = s.rsplit('\n').next().unwrap().chars().count() + 1;
+    Position { line, column }
+}
+
+fn tokenize(s: &str) -> Result<Vec<(Token, Position)>, DotParseError> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    let mut position = Position { line: 1, column: 1 };
+    let mut line_start = true;
+    macro_rules! bump {
+        () => {{
+            let c = chars.next();
+            if c == Some('\n') {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+            c
+        }};
+    }
+    while let Some(&c) = chars.peek() {
+        let start = position;
+        if c == '\n' {
+            bump!();
+            line_start = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            bump!();
+            continue;
+        }
+        if c == '#' && line_start {
+            while chars.peek().is_some_and(|&c| c != '\n') {
+                bump!();
+            }
+            continue;
+        }
+        line_start = false;
+        let token = match c {
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Equals,
+            ';' => Token::Semicolon,
+            ',' => Token::Comma,
+            '"' => {
+                bump!();
+                let mut text = String::new();
+                loop {
+                    match bump!() {
+                        None => return Err(start.error("unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => match bump!() {
+                            None => return Err(start.error("unterminated string")),
+                            Some('"') => text.push('"'),
+                            Some('\\') => text.push('\\'),
+                            Some('n') => text.push('\n'),
+                            Some('\n') => (),
+                            Some(c) => {
+                                text.push('\\');
+                                text.push(c);
+                            }
+                        },
+                        Some(c) => text.push(c),
+                    }
+                }
+                tokens.push((Token::Id { text, quoted: true }, start));
+                continue;
+            }
+            '-' | '/' => {
+                bump!();
+                match (c, chars.peek()) {
+                    ('-', Some('>')) => {
+                        bump!();
+                        tokens.push((Token::Arrow, start));
+                    }
+                    ('-', Some('-')) => {
+                        bump!();
+                        tokens.push((Token::Undirected, start));
+                    }
+                    ('-', Some(c)) if c.is_ascii_digit() || *c == '.' => {
+                        let mut text = String::from("-");
+                        while let Some(&c) = chars.peek().filter(|c| is_id_char(**c)) {
+                            text.push(c);
+                            bump!();
+                        }
+                        tokens.push((
+                            Token::Id {
+                                text,
+                                quoted: false,
+                            },
+                            start,
+                        ));
+                    }
+                    ('/', Some('/')) => {
+                        while chars.peek().is_some_and(|&c| c != '\n') {
+                            bump!();
+                        }
+                    }
+                    ('/', Some('*')) => {
+                        bump!();
+                        let mut last = None;
+                        loop {
+                            match bump!() {
+                                None => return Err(start.error("unterminated comment")),
+                                Some('/') if last == Some('*') => break,
+                                c => last = c,
+                            }
+                        }
+                    }
+                    _ => return Err(start.error(format!("unexpected character `{c}`"))),
+                }
+                continue;
+            }
+            c if is_id_char(c) => {
+                let mut text = String::new();
+                while let Some(&c) = chars.peek().filter(|c| is_id_char(**c)) {
+                    text.push(c);
+                    bump!();
+                }
+                tokens.push((
+                    Token::Id {
+                        text,
+                        quoted: false,
+                    },
+                    start,
+                ));
+                continue;
+            }
+            c => return Err(start.error(format!("unexpected character `{c}`"))),
+        };
+        bump!();
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, Position)],
+    next: usize,
+    end: Position,
+    nodes: NodeArray<SimpleNode<DotAttrs>>,
+    names: NodeNames,
+}
+impl Parser<'_> {
+    fn parse(mut self) -> Result<(Graph<SimpleNode<DotAttrs>>, NodeNames), DotParseError> {
+        let (token, position) = self.bump()?;
+        match token.keyword().as_deref() {
+            Some("digraph") => (),
+            Some("graph") => return Err(position.error("undirected graphs are not supported")),
+            Some("strict") => return Err(position.error("strict graphs are not supported")),
+            _ => return Err(position.error("expected `digraph`")),
+        }
+        if let Some((Token::Id { .. }, _)) = self.peek() {
+            self.bump()?;
+        }
+        self.expect(Token::LBrace, "`{`")?;
+        loop {
+            let (token, position) = self.bump()?;
+            match token {
+                Token::RBrace => break,
+                Token::Semicolon => continue,
+                Token::LBrace => return Err(position.error("subgraphs are not supported")),
+                Token::Id { text, quoted } => self.statement(text, quoted, position)?,
+                _ => return Err(position.error("expected a statement")),
+            }
+        }
+        if let Some((_, position)) = self.peek() {
+            return Err(position.error("unexpected input after the graph"));
+        }
+        Ok((Graph::new(self.nodes), self.names))
+    }
+
+    fn statement(
+        &mut self,
+        id: String,
+        quoted: bool,
+        position: Position,
+    ) -> Result<(), DotParseError> {
+        if !quoted {
+            match id.to_ascii_lowercase().as_str() {
+                "subgraph" => return Err(position.error("subgraphs are not supported")),
+                "node" | "edge" | "graph" => {
+                    return Err(position.error(format!(
+                        "`{id}` default attribute statements are not supported"
+                    )))
+                }
+                _ => (),
+            }
+        }
+        match self.peek() {
+            Some((Token::Equals, _)) => {
+                self.bump()?;
+                self.id()?;
+            }
+            Some((Token::Arrow, _)) => {
+                let mut parent = self.node(id);
+                while let Some((Token::Arrow, _)) = self.peek() {
+                    self.bump()?;
+                    if let Some((token, position)) = self.peek() {
+                        if *token == Token::LBrace || token.keyword().as_deref() == Some("subgraph")
+                        {
+                            return Err(position.error("subgraphs are not supported"));
+                        }
+                    }
+                    let child = self.id()?;
+                    let child = self.node(child);
+                    self.nodes[parent].children.push(child);
+                    parent = child;
+                }
+                if let Some((Token::Undirected, position)) = self.peek() {
+                    return Err(position.error("undirected edges are not supported"));
+                }
+                self.attrs()?;
+            }
+            Some((Token::Undirected, position)) => {
+                return Err(position.error("undirected edges are not supported"))
+            }
+            _ => {
+                let node = self.node(id);
+                let attrs = self.attrs()?;
+                self.nodes[node].data.extend(attrs);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the attribute lists following a statement, if any
+    fn attrs(&mut self) -> Result<DotAttrs, DotParseError> {
+        let mut attrs = DotAttrs::new();
+        while let Some((Token::LBracket, _)) = self.peek() {
+            self.bump()?;
+            loop {
+                if let Some((Token::RBracket, _)) = self.peek() {
+                    self.bump()?;
+                    break;
+                }
+                let key = self.id()?;
+                self.expect(Token::Equals, "`=`")?;
+                let value = self.id()?;
+                attrs.insert(key, value);
+                if let Some((Token::Comma | Token::Semicolon, _)) = self.peek() {
+                    self.bump()?;
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn node(&mut self, name: String) -> NodeIdx {
+        *self
+            .names
+            .entry(name)
+            .or_insert_with(|| self.nodes.insert(SimpleNode::new(DotAttrs::new())))
+    }
+
+    fn id(&mut self) -> Result<String, DotParseError> {
+        match self.bump()? {
+            (Token::Id { text, .. }, _) => Ok(text),
+            (_, position) => Err(position.error("expected an id")),
+        }
+    }
+
+    fn expect(&mut self, expected: Token, name: &str) -> Result<(), DotParseError> {
+        let (token, position) = self.bump()?;
+        if token != expected {
+            return Err(position.error(format!("expected {name}")));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&(Token, Position)> {
+        self.tokens.get(self.next)
+    }
+
+    fn bump(&mut self) -> Result<(Token, Position), DotParseError> {
+        let token = self
+            .tokens
+            .get(self.next)
+            .cloned()
+            .ok_or_else(|| self.end.error("unexpected end of input"))?;
+        self.next += 1;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::{tests::build_graph, to_dot_with, DotEdgeAttrs, DotNodeAttrs, Node};
+
+    use super::*;
+
+    #[test]
+    fn test_from_dot_round_trip() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (0, 2), (3, 3)]);
+        let dot = to_dot_with(
+            &graph,
+            |i, _| DotNodeAttrs {
+                label: Some(format!(
+                    "node \"{}\"\\\nnext",
+                    idx.iter().position(|&n| n == i).unwrap()
+                )),
+                shape: Some("box".into()),
+                ..Default::default()
+            },
+            |_, _| DotEdgeAttrs {
+                style: Some("dashed".into()),
+                ..Default::default()
+            },
+        );
+        let (parsed, names) = from_dot(&dot).unwrap();
+        assert_eq!(parsed.nodes().len(), 4);
+        let key = |i: NodeIdx| names[&format!("{i:?}")];
+        for (n, &i) in idx.iter().enumerate() {
+            let node = &parsed.nodes()[key(i)];
+            let children: Vec<NodeIdx> = graph.nodes()[i]
+                .children()
+                .iter()
+                .map(|&c| key(c))
+                .collect();
+            assert_eq!(node.children, children);
+            assert_eq!(node.data["label"], format!("node \"{n}\"\\\nnext"));
+            assert_eq!(node.data["shape"], "box");
+        }
+    }
+
+    #[test]
+    fn test_from_dot_hand_written() {
+        let dot = r#"
+            // dependencies
+            digraph deps {
+                rankdir=LR;
+                # a comment
+                a -> b -> c [color=red];
+                b [label="B", shape=box]; c
+                "quoted id" -> a /* inline */
+                lonely [label=1.5]
+                b [color="blue"]
+            }
+        "#;
+        let (graph, names) = from_dot(dot).unwrap();
+        assert_eq!(graph.nodes().len(), 5);
+        let node = |name: &str| &graph.nodes()[names[name]];
+        assert_eq!(node("a").children, [names["b"]]);
+        assert_eq!(node("b").children, [names["c"]]);
+        assert_eq!(node("quoted id").children, [names["a"]]);
+        assert!(node("c").data.is_empty());
+        let b: Vec<(&str, &str)> = node("b")
+            .data
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(b, [("color", "blue"), ("label", "B"), ("shape", "box")]);
+        assert_eq!(node("lonely").data["label"], "1.5");
+    }
+
+    #[test]
+    fn test_from_dot_into_adj_nodes() {
+        let (graph, names) =
+            from_dot("digraph {\n  \"a\" -> \"b\"\n  \"b\" -> \"a\"\n  \"c\"\n}").unwrap();
+        let (adj, mapping) = graph.map(|_, _| crate::AdjNode::default());
+        let node = |name: &str| &adj.nodes()[mapping[names[name]]];
+        assert_eq!(node("a").children, [mapping[names["b"]]]);
+        assert_eq!(node("b").children, [mapping[names["a"]]]);
+        assert!(node("c").children.is_empty());
+        let e = from_dot("digraph {\n  \"a\" -> -> \"b\"\n}").unwrap_err();
+        assert_eq!((e.line, e.column), (2, 10));
+    }
+
+    #[test]
+    fn test_from_dot_errors() {
+        let err = |dot: &str| from_dot(dot).unwrap_err();
+        let e = err("digraph {\n  subgraph cluster_a { a }\n}");
+        assert_eq!((e.line, e.column), (2, 3));
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 3: subgraphs are not supported"
+        );
+        let e = err("digraph {\n a -> b\n b -- c\n}");
+        assert_eq!(
+            (e.line, e.column, e.message.as_str()),
+            (3, 4, "undirected edges are not supported")
+        );
+        assert_eq!(
+            err("graph { a -- b }").message,
+            "undirected graphs are not supported"
+        );
+        assert_eq!(err("digraph { node [shape=box] }").column, 11);
+        assert_eq!(err("digraph { a -> }").message, "expected an id");
+        for dot in [
+            "digraph {\n a -> {b c}\n}",
+            "digraph { a -> subgraph { b } }",
+        ] {
+            assert_eq!(err(dot).message, "subgraphs are not supported");
+        }
+        assert_eq!(err("digraph { a -> {b c} }").column, 16);
+        assert_eq!(
+            err("Graph { a -- b }").message,
+            "undirected graphs are not supported"
+        );
+        assert_eq!(
+            err("STRICT digraph {}").message,
+            "strict graphs are not supported"
+        );
+        assert_eq!(
+            err("digraph { Node [shape=box] }").message,
+            "`Node` default attribute statements are not supported"
+        );
+        assert_eq!(
+            err("digraph { SubGraph { a } }").message,
+            "subgraphs are not supported"
+        );
+        let (graph, names) = from_dot("DiGraph { \"node\" -> Edge }").unwrap();
+        assert_eq!(graph.nodes()[names["node"]].children, [names["Edge"]]);
+        let e = err("digraph {\n a\n");
+        assert_eq!(
+            (e.line, e.column, e.message.as_str()),
+            (3, 1, "unexpected end of input")
+        );
+        assert_eq!(err("digraph { \"a }").message, "unterminated string");
+        assert_eq!(err("digraph { a:p }").message, "unexpected character `:`");
+        assert_eq!(
+            err("digraph { } x").message,
+            "unexpected input after the graph"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    checked_dense_ids, AdjNode, Graph, MissingNodeError, Node, NodeArray, NodeIdx, SimpleNode,
+};
+
+/// A line of an edge list is neither `parent child` nor `node <id>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line: usize,
+    pub content: String,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected `parent child` or `node <id>`, found `{}`",
+            self.line, self.content
+        )
+    }
+}
+impl std::error::Error for ParseError {}
+
+/// Return one `node <id>` line per node followed by one `parent child` line per edge
+///
+/// Nodes are numbered from 0 in iteration order. A child that is not in the graph has no number,
+/// so it is an error
+pub fn to_edge_list<T: Node>(graph: &Graph<T>) -> Result<String, MissingNodeError> {
+    let ids = checked_dense_ids(graph)?;
+    let mut out = String::new();
+    for id in 0..ids.len() {
+        out.push_str(&format!("node {id}\n"));
+    }
+    for (i, child) in graph.edges() {
+        out.push_str(&format!("{} {}\n", ids[i], ids[child]));
+    }
+    Ok(out)
+}
+
+/// Parse the format of [`to_edge_list`]
+///
+/// Ids may be any tokens without whitespace other than the keyword `node`, and nodes are created the first time their id
+/// appears; the returned list holds the nodes in that order, so it is indexed by the dense ids of
+/// [`to_edge_list`]. Blank lines and lines starting with `#` are skipped
+pub fn from_edge_list(s: &str) -> Result<(Graph<SimpleNode<()>>, Vec<NodeIdx>), ParseError> {
+    let mut nodes = NodeArray::with_key();
+    let mut keys = vec![];
+    let mut names = HashMap::new();
+    let mut key = |name: &str| {
+        *names.entry(name.to_string()).or_insert_with(|| {
+            let key = nodes.insert(SimpleNode::new(()));
+            keys.push(key);
+            key
+        })
+    };
+    let mut edges = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens[..] {
+            ["node", id] if id != "node" => {
+                key(id);
+            }
+            [parent, child] if ![parent, child].contains(&"node") => {
+                edges.push((key(parent), key(child)))
+            }
+            _ => {
+                return Err(ParseError {
+                    line: i + 1,
+                    content: line.to_string(),
+                })
+            }
+        }
+    }
+    for (parent, child) in edges {
+        nodes[parent].children.push(child);
+    }
+    Ok((Graph::new(nodes), keys))
+}
+
+impl Graph<AdjNode> {
+    /// Build a graph from `(parent, child)` pairs of integer ids
+    ///
+    /// A node is created per distinct id, in order of first appearance; the returned map
+    /// translates ids to keys
+    pub fn from_edges<I: IntoIterator<Item = (u64, u64)>>(
+        edges: I,
+    ) -> (Graph<AdjNode>, HashMap<u64, NodeIdx>) {
+        let mut nodes = NodeArray::with_key();
+        let mut ids = HashMap::new();
+        for (parent, child) in edges {
+            let mut key = |id| {
+                *ids.entry(id)
+                    .or_insert_with(|| nodes.insert(AdjNode { children: vec![] }))
+            };
+            let (parent, child) = (key(parent), key(child));
+            nodes[parent].children.push(child);
+        }
+        (Graph::new(nodes), ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_edge_list_round_trip() {
+        let arena = Bump::new();
+        let (graph, _) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (0, 2)]);
+        let text = to_edge_list(&graph).unwrap();
+        assert_eq!(text, "node 0\nnode 1\nnode 2\nnode 3\n0 1\n0 2\n1 2\n2 0\n");
+        let (parsed, keys) = from_edge_list(&text).unwrap();
+        assert_eq!(keys.len(), 4);
+        assert!(parsed.nodes()[keys[3]].children.is_empty());
+        assert_eq!(to_edge_list(&parsed).unwrap(), text);
+    }
+
+    #[test]
+    fn test_from_edges() {
+        let (graph, ids) = Graph::from_edges([(10, 20), (20, 30), (10, 30), (30, 10), (10, 20)]);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 5);
+        assert_eq!(graph[ids[&10]].children, [ids[&20], ids[&30], ids[&20]]);
+        assert_eq!(graph[ids[&30]].children, [ids[&10]]);
+
+        let (graph, ids) = Graph::from_edges([(1, 2), (3, 3)]);
+        assert!(graph[ids[&2]].children.is_empty());
+        assert_eq!(graph[ids[&3]].children, [ids[&3]]);
+
+        let (graph, ids) = Graph::from_edges([]);
+        assert_eq!(graph.node_count(), 0);
+        assert!(ids.is_empty());
+    }
+
+    #[test]
+    fn test_from_edge_list() {
+        let text = "# deps\n\na b\n  b c  \nnode d\n# trailing comment\nc a\n";
+        let (graph, keys) = from_edge_list(text).unwrap();
+        assert_eq!(keys.len(), 4);
+        assert_eq!(graph.nodes()[keys[0]].children, [keys[1]]);
+        assert_eq!(graph.nodes()[keys[1]].children, [keys[2]]);
+        assert_eq!(graph.nodes()[keys[2]].children, [keys[0]]);
+        assert!(graph.nodes()[keys[3]].children.is_empty());
+
+        let err = from_edge_list("a b\n\na b c\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(
+            err.to_string(),
+            "line 3: expected `parent child` or `node <id>`, found `a b c`"
+        );
+        assert_eq!(from_edge_list("node\n").unwrap_err().line, 1);
+        assert_eq!(
+            from_edge_list("# x\nnode a\nnode b\nnode a b\n")
+                .unwrap_err()
+                .line,
+            4
+        );
+        for line in ["node node", "a node", "node"] {
+            assert_eq!(from_edge_list(line).unwrap_err().content, line);
+        }
+    }
+
+    #[test]
+    fn test_to_edge_list_dangling_child() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(SimpleNode::new(()));
+        let b = graph.insert_child(a, SimpleNode::new(()));
+        graph.nodes_mut().remove(b);
+        assert_eq!(
+            to_edge_list(&graph).unwrap_err(),
+            MissingNodeError {
+                node: b,
+                parent: Some(a)
+            }
+        );
+    }
+}
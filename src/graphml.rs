@@ -0,0 +1,175 @@
+use std::{collections::HashMap, io};
+
+use crate::{checked_dense_ids, Graph, Node, NodeIdx};
+
+/// Return a GraphML document with one string `<data>` element per attribute returned by `attrs`
+///
+/// Nodes are named `n0`, `n1`, ... in iteration order and attribute keys `d0`, `d1`, ... in
+/// order of first appearance
+///
+/// # Panics
+///
+/// Panics on a child that is not in the graph; see [`write_graphml`]
+pub fn to_graphml<T: Node>(
+    graph: &Graph<T>,
+    attrs: impl Fn(NodeIdx, &T) -> Vec<(String, String)>,
+) -> String {
+    let mut out = vec![];
+    write_graphml(graph, &mut out, attrs).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Same as [`to_graphml`] but stream the output into `w`
+///
+/// The attributes of every node are collected first since the `<key>` declarations must precede
+/// the nodes. A child that is not in the graph is an [`io::ErrorKind::InvalidData`] error, reported
+/// before anything is written
+pub fn write_graphml<T: Node, W: io::Write>(
+    graph: &Graph<T>,
+    w: &mut W,
+    attrs: impl Fn(NodeIdx, &T) -> Vec<(String, String)>,
+) -> io::Result<()> {
+    let ids =
+        checked_dense_ids(graph).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let node_attrs: Vec<Vec<(String, String)>> = graph
+        .nodes()
+        .iter()
+        .map(|(i, node)| attrs(i, node))
+        .collect();
+    let mut keys: HashMap<&str, usize> = HashMap::new();
+    let mut key_names = vec![];
+    for (name, _) in node_attrs.iter().flatten() {
+        keys.entry(name).or_insert_with(|| {
+            key_names.push(name);
+            key_names.len() - 1
+        });
+    }
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (key, name) in key_names.iter().enumerate() {
+        let name = escape(name);
+        writeln!(
+            w,
+            r#"  <key id="d{key}" for="node" attr.name="{name}" attr.type="string"/>"#
+        )?;
+    }
+    writeln!(w, r#"  <graph id="G" edgedefault="directed">"#)?;
+    for (i, attrs) in graph.nodes().keys().zip(&node_attrs) {
+        if attrs.is_empty() {
+            writeln!(w, r#"    <node id="n{}"/>"#, ids[i])?;
+            continue;
+        }
+        writeln!(w, r#"    <node id="n{}">"#, ids[i])?;
+        for (name, value) in attrs {
+            let key = keys[name.as_str()];
+            writeln!(w, r#"      <data key="d{key}">{}</data>"#, escape(value))?;
+        }
+        writeln!(w, "    </node>")?;
+    }
+    for (i, child) in graph.edges() {
+        writeln!(
+            w,
+            r#"    <edge source="n{}" target="n{}"/>"#,
+            ids[i], ids[child]
+        )?;
+    }
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")
+}
+
+/// Escape `s` for use in XML text and attribute values
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    /// Check that tags are balanced and that text and attribute values only use known entities,
+    /// returning the text content of every element
+    fn check_well_formed(xml: &str) -> Vec<String> {
+        let mut open = vec![];
+        let mut texts = vec![];
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            texts.push(rest[..start].trim().to_string());
+            let end = start + rest[start..].find('>').unwrap();
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            let name = tag.split_whitespace().next().unwrap();
+            let quoted: Vec<&str> = tag.split('"').skip(1).step_by(2).collect();
+            assert!(quoted.iter().all(|value| !value.contains('<')));
+            if tag.starts_with('?') || tag.ends_with('/') {
+                continue;
+            }
+            match name.strip_prefix('/') {
+                Some(name) => assert_eq!(open.pop(), Some(name)),
+                None => open.push(name),
+            }
+        }
+        assert!(open.is_empty());
+        texts.retain(|text| !text.is_empty());
+        for text in &texts {
+            let mut parts = text.split('&').skip(1);
+            assert!(parts.all(|part| ["lt;", "gt;", "amp;", "quot;", "apos;"]
+                .iter()
+                .any(|entity| part.starts_with(entity))));
+        }
+        texts
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (0, 2)]);
+        let xml = to_graphml(&graph, |i, _| {
+            let mut attrs = vec![("label".to_string(), "<a & \"b\">".to_string())];
+            if i == idx[1] {
+                attrs.push(("weight".into(), "2".into()));
+            }
+            if i == idx[2] {
+                attrs.clear();
+            }
+            attrs
+        });
+        let texts = check_well_formed(&xml);
+        let label = "&lt;a &amp; &quot;b&quot;&gt;";
+        assert_eq!(texts, [label, label, "2"]);
+        assert!(xml.contains(r#"<key id="d0" for="node" attr.name="label" attr.type="string"/>"#));
+        assert!(xml.contains(r#"<key id="d1" for="node" attr.name="weight" attr.type="string"/>"#));
+        assert!(xml.find("<key").unwrap() < xml.find("<node").unwrap());
+        assert!(xml.contains(r#"<node id="n2"/>"#));
+        assert!(xml.contains(r#"<edge source="n0" target="n2"/>"#));
+        assert_eq!(xml.matches("<edge ").count(), 3);
+    }
+
+    #[test]
+    fn test_write_graphml_dangling_child() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2)]);
+        graph.nodes_mut().remove(idx[2]);
+        let mut out = vec![];
+        let err = write_graphml(&graph, &mut out, |_, _| vec![]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{checked_dense_ids, AdjNode, Graph, MissingNodeError, Node, NodeArray, NodeIdx};
+
+/// JSON input [`from_json_adjacency`] cannot read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonParseError {
+    /// 1-based line of the offending character
+    pub line: usize,
+    /// 1-based column of the offending character, in characters
+    pub column: usize,
+    pub message: String,
+}
+impl fmt::Display for JsonParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+impl std::error::Error for JsonParseError {}
+
+/// Return the graph as `{"nodes":[0,1,...],"edges":[[parent,child],...]}`
+///
+/// Nodes are numbered from 0 in iteration order, the same as [`to_edge_list`](crate::to_edge_list).
+/// A child that is not in the graph has no number, so it is an error
+pub fn to_json_adjacency<T: Node>(graph: &Graph<T>) -> Result<String, MissingNodeError> {
+    let ids = checked_dense_ids(graph)?;
+    let nodes: Vec<String> = (0..ids.len()).map(|id| id.to_string()).collect();
+    let edges: Vec<String> = graph
+        .edges()
+        .map(|(parent, child)| format!("[{},{}]", ids[parent], ids[child]))
+        .collect();
+    Ok(format!(
+        "{{\"nodes\":[{}],\"edges\":[{}]}}",
+        nodes.join(","),
+        edges.join(",")
+    ))
+}
+
+/// Parse the format of [`to_json_adjacency`]
+///
+/// Node ids may be any non-negative integers and both keys are optional but may appear only once. The returned list holds
+/// the nodes in the order of `"nodes"`, so it is indexed by the dense ids of
+/// [`to_json_adjacency`]. Edges must refer to listed nodes
+pub fn from_json_adjacency(s: &str) -> Result<(Graph<AdjNode>, Vec<NodeIdx>), JsonParseError> {
+    let mut parser = Parser { s, pos: 0 };
+    let mut ids = None;
+    let mut edges = None;
+    parser.expect(b'{')?;
+    if !parser.eat(b'}') {
+        loop {
+            let key_pos = parser.skip_whitespace();
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            match key {
+                "nodes" if ids.is_none() => {
+                    ids = Some(parser.list(|parser| Ok((parser.skip_whitespace(), parser.id()?)))?)
+                }
+                "edges" if edges.is_none() => {
+                    edges = Some(parser.list(|parser| {
+                        let pos = parser.skip_whitespace();
+                        parser.expect(b'[')?;
+                        let parent = parser.id()?;
+                        parser.expect(b',')?;
+                        let child = parser.id()?;
+                        parser.expect(b']')?;
+                        Ok((pos, parent, child))
+                    })?)
+                }
+                "nodes" | "edges" => {
+                    return Err(parser.error_at(key_pos, format!("duplicate key `{key}`")))
+                }
+                _ => return Err(parser.error_at(key_pos, format!("unknown key `{key}`"))),
+            }
+            if parser.eat(b'}') {
+                break;
+            }
+            parser.expect(b',')?;
+        }
+    }
+    let end = parser.skip_whitespace();
+    if end < s.len() {
+        return Err(parser.error_at(end, "trailing characters"));
+    }
+
+    let mut nodes = NodeArray::with_key();
+    let mut keys = vec![];
+    let mut by_id = HashMap::new();
+    for (pos, id) in ids.unwrap_or_default() {
+        let key = nodes.insert(AdjNode { children: vec![] });
+        if by_id.insert(id, key).is_some() {
+            return Err(parser.error_at(pos, format!("duplicate node {id}")));
+        }
+        keys.push(key);
+    }
+    for (pos, parent, child) in edges.unwrap_or_default() {
+        let [Some(&parent), Some(&child)] = [by_id.get(&parent), by_id.get(&child)] else {
+            return Err(parser.error_at(pos, "edge refers to an unlisted node"));
+        };
+        nodes[parent].children.push(child);
+    }
+    Ok((Graph::new(nodes), keys))
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    /// Byte offset of the next character
+    pos: usize,
+}
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) -> usize {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.pos
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonParseError> {
+        if self.eat(byte) {
+            return Ok(());
+        }
+        Err(self.error_at(self.pos, format!("expected `{}`", byte as char)))
+    }
+
+    /// Parse a string without decoding its escapes
+    fn string(&mut self) -> Result<&'a str, JsonParseError> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        let mut escaped = false;
+        for (i, c) in self.s[start..].char_indices() {
+            match c {
+                '"' if !escaped => {
+                    self.pos = start + i + 1;
+                    return Ok(&self.s[start..start + i]);
+                }
+                '\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        Err(self.error_at(start - 1, "unterminated string"))
+    }
+
+    fn id(&mut self) -> Result<u64, JsonParseError> {
+        let start = self.skip_whitespace();
+        let rest = &self.s[start..];
+        let len = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let Ok(id) = rest[..len].parse() else {
+            return Err(self.error_at(start, "expected a non-negative integer id"));
+        };
+        self.pos += len;
+        Ok(id)
+    }
+
+    /// Parse a `[...]` array whose elements are read by `element`
+    fn list<E>(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> Result<E, JsonParseError>,
+    ) -> Result<Vec<E>, JsonParseError> {
+        self.expect(b'[')?;
+        let mut elements = vec![];
+        if self.eat(b']') {
+            return Ok(elements);
+        }
+        loop {
+            elements.push(element(self)?);
+            if self.eat(b']') {
+                return Ok(elements);
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> JsonParseError {
+        let before = &self.s[..pos];
+        JsonParseError {
+            line: before.split('\n').count(),
+            column: before.rsplit('\n').next().unwrap().chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_json_adjacency_round_trip() {
+        let arena = Bump::new();
+        let (graph, _) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (0, 2), (1, 1)]);
+        let json = to_json_adjacency(&graph).unwrap();
+        assert_eq!(
+            json,
+            r#"{"nodes":[0,1,2,3],"edges":[[0,1],[0,2],[1,2],[1,1],[2,0]]}"#
+        );
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["edges"][4], serde_json::json!([2, 0]));
+        let (parsed, keys) = from_json_adjacency(&json).unwrap();
+        assert_eq!(keys.len(), 4);
+        assert!(parsed.nodes()[keys[3]].children.is_empty());
+        assert_eq!(to_json_adjacency(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn test_from_json_adjacency() {
+        let json = "{\n  \"edges\": [ [7, 3] , [3,7] ],\n  \"nodes\": [7, 3, 10]\n}\n";
+        let (graph, keys) = from_json_adjacency(json).unwrap();
+        assert_eq!(graph.nodes()[keys[0]].children, [keys[1]]);
+        assert_eq!(graph.nodes()[keys[1]].children, [keys[0]]);
+        assert!(graph.nodes()[keys[2]].children.is_empty());
+        assert_eq!(from_json_adjacency("{}").unwrap().1.len(), 0);
+
+        let err = from_json_adjacency("{\"nodes\": [0],\n \"edges\": [[0, 1]]}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 12: edge refers to an unlisted node"
+        );
+        let err = from_json_adjacency("{\"nodes\": [0, -1]}").unwrap_err();
+        assert_eq!(err.column, 15);
+        let err = from_json_adjacency("{\"nodes\": [1, 1]}").unwrap_err();
+        assert_eq!(err.message, "duplicate node 1");
+        let err = from_json_adjacency("{\"weights\": []}").unwrap_err();
+        assert_eq!(err.message, "unknown key `weights`");
+        let err =
+            from_json_adjacency("{\"nodes\": [0],\n \"edges\": [], \"nodes\": [1]}").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 15: duplicate key `nodes`");
+        let err = from_json_adjacency("{\"edges\": [], \"edges\": []}").unwrap_err();
+        assert_eq!(err.message, "duplicate key `edges`");
+        let err = from_json_adjacency("{} x").unwrap_err();
+        assert_eq!(err.message, "trailing characters");
+        let err = from_json_adjacency("{\"nodes\": [0]").unwrap_err();
+        assert_eq!(err.message, "expected `,`");
+    }
+
+    #[test]
+    fn test_to_json_adjacency_dangling_child() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(AdjNode::default());
+        let b = graph.insert_child(a, AdjNode::default());
+        graph.nodes_mut().remove(b);
+        assert_eq!(
+            to_json_adjacency(&graph).unwrap_err(),
+            MissingNodeError {
+                node: b,
+                parent: Some(a)
+            }
+        );
+    }
+}
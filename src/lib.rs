@@ -1,7 +1,78 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    fmt,
+    ops::{Index, IndexMut},
+};
 
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 
+mod builder;
+mod components;
+mod cycle;
+mod dataflow;
+mod degree;
+mod dot;
+mod dot_parse;
+mod edge_list;
+mod graphml;
+mod json;
+mod mermaid;
+mod path;
+#[cfg(feature = "petgraph")]
+mod petgraph_interop;
+#[cfg(feature = "serde")]
+mod portable;
+mod reach;
+mod topo;
+mod transform;
+mod traversal;
+mod validate;
+pub use builder::{BuildError, GraphBuilder, NodeNames};
+pub use components::{
+    component_count, condensation, connected_components, strongly_connected_components, SccNode,
+};
+pub use cycle::{detect_cycle, find_cycle, is_cyclic};
+pub use dataflow::{fixed_point, fixed_point_limited, IterationLimit};
+pub use degree::{degrees, in_degree, leaves, out_degree, roots, Degree};
+pub use dot::{
+    to_dot, to_dot_labeled, to_dot_opts, to_dot_with, write_dot, write_dot_with, ClusterFn,
+    DotEdgeAttrs, DotNodeAttrs, DotOptions, RankDir,
+};
+pub use dot_parse::{from_dot, DotAttrs, DotParseError};
+pub use edge_list::{from_edge_list, to_edge_list, ParseError};
+pub use graphml::{to_graphml, write_graphml};
+pub use json::{from_json_adjacency, to_json_adjacency, JsonParseError};
+pub use mermaid::{to_mermaid, to_mermaid_opts, MermaidOptions};
+pub use path::{
+    all_simple_paths, all_simple_paths_iter, bellman_ford, bfs_distances, bfs_predecessors,
+    count_paths, count_paths_to_all, critical_path, dijkstra, dijkstra_by, dijkstra_path,
+    dijkstra_path_by, earliest_completion_times, iterative_deepening, longest_path,
+    longest_path_unweighted, multi_source_bfs, neighborhood, shortest_path, try_dijkstra_by,
+    AllSimplePaths, CountPathsError, NegativeCycle, NegativeWeight,
+};
+#[cfg(feature = "petgraph")]
+pub use petgraph_interop::{from_petgraph, to_petgraph};
+#[cfg(feature = "serde")]
+pub use portable::{
+    deserialize_graph, deserialize_graph_with, serialize_graph, ChildOutOfRange, PortableGraph,
+    PortableNode,
+};
+pub use reach::{ancestors, descendants, is_reachable, lowest_common_ancestor, transitive_closure};
+pub use topo::{
+    dependency_layers, dependency_order_stable, topological_sort, topological_sort_all, CycleError,
+    TopologicalSortError,
+};
+pub use transform::{
+    induced_subgraph, reachable_subgraph, to_adjacency_matrix, transpose, ReverseIndex,
+};
+pub use traversal::{
+    bfs_iter, classify_edges, depth_first_events, depth_first_search_limited,
+    depth_first_search_post_order, depth_first_search_unique, depth_first_search_with, dfs_iter,
+    order_index, reverse_post_order, try_depth_first_search_post_order,
+    try_depth_first_search_with, Bfs, BfsIter, Dfs, DfsEvent, DfsIter, EdgeKind,
+};
+pub use validate::{Severity, ValidationError, ValidationKind};
+
 pub type NodeArray<T> = SlotMap<NodeIdx, T>;
 
 new_key_type! {
@@ -23,28 +94,263 @@ impl<T> Graph<T> {
     pub fn nodes_mut(&mut self) -> &mut NodeArray<T> {
         &mut self.nodes
     }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+impl<T> Index<NodeIdx> for Graph<T> {
+    type Output = T;
+
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the graph
+    fn index(&self, node: NodeIdx) -> &T {
+        &self.nodes[node]
+    }
+}
+impl<T> IndexMut<NodeIdx> for Graph<T> {
+    fn index_mut(&mut self, node: NodeIdx) -> &mut T {
+        &mut self.nodes[node]
+    }
+}
+impl<T: Node> Graph<T> {
+    /// Return every edge as a `(parent, child)` pair, once per occurrence of the child
+    pub fn edges(&self) -> impl Iterator<Item = (NodeIdx, NodeIdx)> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|(parent, node)| node.children().iter().map(move |&child| (parent, child)))
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.nodes.values().map(|node| node.children().len()).sum()
+    }
+}
+impl<T: NodeMut> Graph<T> {
+    /// Remove `node` along with every edge pointing to it
+    pub fn remove_node(&mut self, node: NodeIdx) -> Option<T> {
+        let removed = self.nodes.remove(node)?;
+        for other in self.nodes.values_mut() {
+            other.retain_children(|&mut child| child != node);
+        }
+        Some(removed)
+    }
+
+    /// Remove every node in `nodes` along with every edge pointing to them in a single sweep
+    pub fn remove_nodes(&mut self, nodes: &[NodeIdx]) {
+        let mut removed = SecondaryMap::new();
+        for &node in nodes {
+            if self.nodes.remove(node).is_some() {
+                removed.insert(node, ());
+            }
+        }
+        for other in self.nodes.values_mut() {
+            other.retain_children(|&mut child| !removed.contains_key(child));
+        }
+    }
+
+    /// Keep only the nodes for which `keep` returns `true` and drop every edge to the others
+    pub fn retain_nodes(&mut self, mut keep: impl FnMut(NodeIdx, &T) -> bool) {
+        let mut removed = SecondaryMap::new();
+        self.nodes.retain(|node, value| {
+            let kept = keep(node, value);
+            if !kept {
+                removed.insert(node, ());
+            }
+            kept
+        });
+        for value in self.nodes.values_mut() {
+            value.retain_children(|&mut child| !removed.contains_key(child));
+        }
+    }
+
+    /// Add an edge from `parent` to `child`
+    pub fn add_edge(&mut self, parent: NodeIdx, child: NodeIdx) -> Result<(), MissingNodeError> {
+        if !self.nodes.contains_key(parent) {
+            return Err(MissingNodeError {
+                node: parent,
+                parent: None,
+            });
+        }
+        if !self.nodes.contains_key(child) {
+            return Err(MissingNodeError {
+                node: child,
+                parent: Some(parent),
+            });
+        }
+        self.nodes.get_mut(parent).unwrap().push_child(child);
+        Ok(())
+    }
+
+    /// Remove one edge from `parent` to `child` and return whether there was one
+    pub fn remove_edge(&mut self, parent: NodeIdx, child: NodeIdx) -> bool {
+        let Some(node) = self.nodes.get_mut(parent) else {
+            return false;
+        };
+        let mut removed = false;
+        node.retain_children(|&mut c| {
+            if removed || c != child {
+                return true;
+            }
+            removed = true;
+            false
+        });
+        removed
+    }
+}
+
+/// A node is absent from the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingNodeError {
+    pub node: NodeIdx,
+    /// The node whose children refer to the missing one
+    pub parent: Option<NodeIdx>,
+}
+impl fmt::Display for MissingNodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing node {:?}", self.node)?;
+        if let Some(parent) = self.parent {
+            write!(f, " referenced by {parent:?}")?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for MissingNodeError {}
+
+/// Why [`try_dependency_order_checked`] could not order the nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyOrderError {
+    Cycle(CycleError),
+    MissingNode(MissingNodeError),
+}
+impl fmt::Display for DependencyOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyOrderError::Cycle(e) => e.fmt(f),
+            DependencyOrderError::MissingNode(e) => e.fmt(f),
+        }
+    }
+}
+impl std::error::Error for DependencyOrderError {}
+impl From<CycleError> for DependencyOrderError {
+    fn from(e: CycleError) -> Self {
+        DependencyOrderError::Cycle(e)
+    }
+}
+impl From<MissingNodeError> for DependencyOrderError {
+    fn from(e: MissingNodeError) -> Self {
+        DependencyOrderError::MissingNode(e)
+    }
 }
 
 pub trait Node {
     fn children(&self) -> &[NodeIdx];
 }
 
-pub fn to_dot<T: Node>(graph: &Graph<T>) -> String {
-    let mut dot = String::new();
-    dot.push_str("digraph {\n");
-    for (i, node) in graph.nodes() {
-        for &child in node.children() {
-            dot.push_str(&format!("\"{i:?}\" -> \"{child:?}\"\n"));
+/// A [`Node`] whose edges can be edited
+pub trait NodeMut: Node {
+    fn push_child(&mut self, child: NodeIdx);
+    /// Keep only the children for which `keep` returns `true`, preserving their order
+    ///
+    /// `keep` may also rewrite the children it keeps
+    fn retain_children(&mut self, keep: impl FnMut(&mut NodeIdx) -> bool);
+}
+
+/// A [`Node`] whose edges carry weights
+///
+/// Being a subtrait, any weighted node works with the unweighted algorithms as is
+pub trait WeightedNode: Node {
+    /// The weight of each edge in the same order as [`Node::children`]
+    fn weights(&self) -> &[f64];
+
+    fn weighted_children(&self) -> impl Iterator<Item = (NodeIdx, f64)> + '_ {
+        debug_assert_eq!(self.children().len(), self.weights().len());
+        let weights = self.weights().iter().copied();
+        self.children().iter().copied().zip(weights)
+    }
+}
+
+/// A node holding nothing but its children
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdjNode {
+    pub children: Vec<NodeIdx>,
+}
+impl Node for AdjNode {
+    fn children(&self) -> &[NodeIdx] {
+        &self.children
+    }
+}
+impl NodeMut for AdjNode {
+    fn push_child(&mut self, child: NodeIdx) {
+        self.children.push(child);
+    }
+    fn retain_children(&mut self, keep: impl FnMut(&mut NodeIdx) -> bool) {
+        self.children.retain_mut(keep);
+    }
+}
+
+/// A node carrying a payload next to its children
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimpleNode<D> {
+    pub data: D,
+    pub children: Vec<NodeIdx>,
+}
+impl<D> SimpleNode<D> {
+    pub fn new(data: D) -> Self {
+        Self {
+            data,
+            children: vec![],
         }
     }
-    dot.push('}');
-    dot
+}
+impl<D> Node for SimpleNode<D> {
+    fn children(&self) -> &[NodeIdx] {
+        &self.children
+    }
+}
+impl<D> NodeMut for SimpleNode<D> {
+    fn push_child(&mut self, child: NodeIdx) {
+        self.children.push(child);
+    }
+    fn retain_children(&mut self, keep: impl FnMut(&mut NodeIdx) -> bool) {
+        self.children.retain_mut(keep);
+    }
+}
+impl<D> Graph<SimpleNode<D>> {
+    /// Insert a node without children
+    pub fn insert_node(&mut self, data: D) -> NodeIdx {
+        self.nodes.insert(SimpleNode::new(data))
+    }
+
+    /// Insert a node and add an edge to it from `parent`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is not in the graph
+    pub fn insert_child(&mut self, parent: NodeIdx, data: D) -> NodeIdx {
+        assert!(self.nodes.contains_key(parent), "missing parent {parent:?}");
+        let child = self.insert_node(data);
+        self.nodes.get_mut(parent).unwrap().children.push(child);
+        child
+    }
 }
 
 /// Return the visited nodes in pre-order
 ///
-/// A node can be visited more than once
+/// A node can be visited more than once: it is only skipped while it is on the stack, so a node
+/// reached again through another path after leaving the stack is visited again. Use
+/// [`depth_first_search_unique`] to visit each node at most once
 pub fn depth_first_search<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<NodeIdx> {
+    try_depth_first_search(graph, starts).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`depth_first_search`] but return an error instead of panicking on a missing node
+pub fn try_depth_first_search<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<NodeIdx>, MissingNodeError> {
     let mut in_stack = SecondaryMap::new();
     let mut stack = vec![];
     for &start in starts {
@@ -55,7 +361,7 @@ pub fn depth_first_search<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<
     while let Some(node) = stack.pop() {
         in_stack.remove(node);
         visit.push(node);
-        for &child in graph.nodes().get(node).unwrap().children() {
+        for &child in checked_children(graph, node)? {
             if in_stack.contains_key(child) {
                 continue;
             }
@@ -63,17 +369,86 @@ pub fn depth_first_search<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<
             in_stack.insert(child, ());
         }
     }
-    visit
+    Ok(visit)
+}
+
+/// Number the nodes from 0 in iteration order, as the exporters name them
+pub(crate) fn dense_ids<T>(graph: &Graph<T>) -> SecondaryMap<NodeIdx, usize> {
+    graph.nodes().keys().zip(0..).collect()
+}
+
+/// Same as [`dense_ids`] but first check that every child is in the graph, so every edge can be
+/// numbered
+pub(crate) fn checked_dense_ids<T: Node>(
+    graph: &Graph<T>,
+) -> Result<SecondaryMap<NodeIdx, usize>, MissingNodeError> {
+    for (parent, child) in graph.edges() {
+        if !graph.nodes().contains_key(child) {
+            return Err(MissingNodeError {
+                node: child,
+                parent: Some(parent),
+            });
+        }
+    }
+    Ok(dense_ids(graph))
+}
+
+/// Return the children of `node` after checking that they are all in the graph
+///
+/// A missing `node` itself is reported without a parent
+pub(crate) fn checked_children<T: Node>(
+    graph: &Graph<T>,
+    node: NodeIdx,
+) -> Result<&[NodeIdx], MissingNodeError> {
+    let Some(value) = graph.nodes().get(node) else {
+        return Err(MissingNodeError { node, parent: None });
+    };
+    let children = value.children();
+    if let Some(&child) = children.iter().find(|&&c| !graph.nodes().contains_key(c)) {
+        return Err(MissingNodeError {
+            node: child,
+            parent: Some(node),
+        });
+    }
+    Ok(children)
 }
 
 /// A node can be visited at most once
+///
+/// # Panics
+///
+/// Panics if the nodes reachable from `starts` contain a cycle
 pub fn dependency_order<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<NodeIdx> {
+    try_dependency_order(graph, starts).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`dependency_order`] but return the cycle instead of panicking if there is one
+///
+/// # Panics
+///
+/// Panics if a node reachable from `starts` is missing from the graph
+pub fn try_dependency_order<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<NodeIdx>, CycleError> {
+    try_dependency_order_checked(graph, starts).map_err(|e| match e {
+        DependencyOrderError::Cycle(e) => e,
+        DependencyOrderError::MissingNode(e) => panic!("{e}"),
+    })
+}
+
+/// Same as [`try_dependency_order`] but also return an error instead of panicking on a missing node
+pub fn try_dependency_order_checked<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<NodeIdx>, DependencyOrderError> {
     #[derive(Debug, Clone, Copy)]
     struct Edge {
         pub parent: Option<NodeIdx>,
         pub child: NodeIdx,
     }
     let mut pending_children = SecondaryMap::new();
+    let mut discovered_by = SecondaryMap::new();
     let mut visited = SecondaryMap::new();
     let mut stack = vec![];
     let mut visit = vec![];
@@ -86,7 +461,8 @@ pub fn dependency_order<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<No
     while let Some(edge) = stack.pop() {
         let node = edge.child;
         if !pending_children.contains_key(node) {
-            pending_children.insert(node, graph.nodes().get(node).unwrap().children().len());
+            pending_children.insert(node, checked_children(graph, node)?.len());
+            discovered_by.insert(node, edge.parent);
         }
         if *pending_children.get(node).unwrap() == 0 {
             if !visited.contains_key(node) {
@@ -102,7 +478,17 @@ pub fn dependency_order<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<No
         stack.push(edge);
         for &child in graph.nodes().get(node).unwrap().children() {
             if pending_children.contains_key(child) {
-                assert_eq!(*pending_children.get(child).unwrap(), 0);
+                if *pending_children.get(child).unwrap() != 0 {
+                    // `child` is still waiting on its descendants, one of which is `node`
+                    let mut cycle = vec![node];
+                    let mut current = node;
+                    while current != child {
+                        current = discovered_by.get(current).unwrap().unwrap();
+                        cycle.push(current);
+                    }
+                    cycle.reverse();
+                    return Err(CycleError { cycle }.into());
+                }
                 *pending_children.get_mut(node).unwrap() -= 1;
                 continue;
             }
@@ -112,51 +498,164 @@ pub fn dependency_order<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<No
             });
         }
     }
-    visit
+    Ok(visit)
 }
 
 #[derive(Debug)]
 pub struct VisitParams<'a, T> {
     pub graph: &'a mut Graph<T>,
     pub node: NodeIdx,
+    /// Number of edges from the start on the path the traversal took to `node`
+    pub depth: usize,
+    /// The node whose children led the traversal to `node`; `None` for the start
+    pub parent: Option<NodeIdx>,
 }
 #[derive(Debug, Clone)]
 pub enum NextMove {
     Postpone,
     TerminateBranch,
     VisitChildren,
+    /// End the whole traversal
+    Stop,
+}
+
+/// How a closure-driven traversal went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchSummary {
+    /// Number of visits not answered with [`NextMove::Postpone`]
+    pub visited: usize,
+    /// Whether the traversal ended early on [`NextMove::Stop`]
+    pub stopped: bool,
+}
+
+/// Every node queued by [`try_breadth_first_search`], or every pending sibling in
+/// [`try_depth_first_search_with`], was postponed without any progress in between
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadlock {
+    /// The nodes still queued, in queue order
+    pub queued: Vec<NodeIdx>,
+}
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "every queued node is postponed: {:?}", self.queued)
+    }
 }
+impl std::error::Error for Deadlock {}
 
+/// Why [`try_breadth_first_search`] or [`try_depth_first_search_with`] could not finish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchError {
+    MissingNode(MissingNodeError),
+    Deadlock(Deadlock),
+}
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::MissingNode(e) => e.fmt(f),
+            SearchError::Deadlock(e) => e.fmt(f),
+        }
+    }
+}
+impl std::error::Error for SearchError {}
+impl From<MissingNodeError> for SearchError {
+    fn from(e: MissingNodeError) -> Self {
+        SearchError::MissingNode(e)
+    }
+}
+impl From<Deadlock> for SearchError {
+    fn from(e: Deadlock) -> Self {
+        SearchError::Deadlock(e)
+    }
+}
+
+#[deprecated(note = "use breadth_first_search")]
 pub fn breath_first_search<T: Node>(
     graph: &mut Graph<T>,
     start: NodeIdx,
     visit: &mut impl FnMut(VisitParams<'_, T>) -> NextMove,
-) {
+) -> SearchSummary {
+    breadth_first_search(graph, start, visit)
+}
+
+/// Visit the nodes reachable from `start` in breadth-first order, as directed by `visit`
+///
+/// # Panics
+///
+/// Panics on a missing node or when every queued node is postponed in a row; see
+/// [`try_breadth_first_search`]
+pub fn breadth_first_search<T: Node>(
+    graph: &mut Graph<T>,
+    start: NodeIdx,
+    visit: &mut impl FnMut(VisitParams<'_, T>) -> NextMove,
+) -> SearchSummary {
+    try_breadth_first_search(graph, start, visit).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`breadth_first_search`] but return an error instead of panicking
+///
+/// Once as many visits in a row as there are queued nodes answer [`NextMove::Postpone`], every
+/// queued node is waiting on another one and the traversal stops with [`SearchError::Deadlock`]
+pub fn try_breadth_first_search<T: Node>(
+    graph: &mut Graph<T>,
+    start: NodeIdx,
+    visit: &mut impl FnMut(VisitParams<'_, T>) -> NextMove,
+) -> Result<SearchSummary, SearchError> {
+    let mut summary = SearchSummary {
+        visited: 0,
+        stopped: false,
+    };
     let mut in_queue = SecondaryMap::new();
     let mut queue = VecDeque::new();
-    queue.push_back(start);
+    let mut postponed_in_row = 0;
+    queue.push_back((start, 0, None));
     in_queue.insert(start, ());
-    while let Some(node) = queue.pop_front() {
+    while let Some((node, depth, parent)) = queue.pop_front() {
         in_queue.remove(node);
-        let params = VisitParams { graph, node };
+        if !graph.nodes().contains_key(node) {
+            return Err(MissingNodeError { node, parent }.into());
+        }
+        let params = VisitParams {
+            graph,
+            node,
+            depth,
+            parent,
+        };
         let next_move = visit(params);
         match next_move {
             NextMove::Postpone => {
-                queue.push_back(node);
+                queue.push_back((node, depth, parent));
                 in_queue.insert(node, ());
+                postponed_in_row += 1;
+                if postponed_in_row == queue.len() {
+                    let queued = queue.iter().map(|&(node, _, _)| node).collect();
+                    return Err(Deadlock { queued }.into());
+                }
+                continue;
+            }
+            NextMove::TerminateBranch => {
+                summary.visited += 1;
+                postponed_in_row = 0;
                 continue;
             }
-            NextMove::TerminateBranch => continue,
-            NextMove::VisitChildren => (),
+            NextMove::VisitChildren => {
+                summary.visited += 1;
+                postponed_in_row = 0;
+            }
+            NextMove::Stop => {
+                summary.visited += 1;
+                summary.stopped = true;
+                break;
+            }
         }
-        for &child in graph.nodes().get(node).unwrap().children() {
+        for &child in checked_children(graph, node)? {
             if in_queue.contains_key(child) {
                 continue;
             }
-            queue.push_back(child);
+            queue.push_back((child, depth + 1, Some(node)));
             in_queue.insert(child, ());
         }
     }
+    Ok(summary)
 }
 
 #[cfg(test)]
@@ -167,6 +666,7 @@ mod tests {
 
     use bumpalo::{collections::Vec as BumpVec, Bump};
 
+    #[derive(Debug, Clone)]
     pub struct NodeA<'b> {
         pub children: BumpVec<'b, NodeIdx>,
     }
@@ -175,6 +675,91 @@ mod tests {
             &self.children
         }
     }
+    impl NodeMut for NodeA<'_> {
+        fn push_child(&mut self, child: NodeIdx) {
+            self.children.push(child);
+        }
+        fn retain_children(&mut self, mut keep: impl FnMut(&mut NodeIdx) -> bool) {
+            let mut kept = 0;
+            for i in 0..self.children.len() {
+                if keep(&mut self.children[i]) {
+                    self.children.swap(kept, i);
+                    kept += 1;
+                }
+            }
+            self.children.truncate(kept);
+        }
+    }
+
+    /// Build a graph of `len` nodes connected by `edges` given as `(parent, child)` positions
+    pub fn build_graph<'b>(
+        arena: &'b Bump,
+        len: usize,
+        edges: &[(usize, usize)],
+    ) -> (Graph<NodeA<'b>>, Vec<NodeIdx>) {
+        let mut nodes = NodeArray::with_key();
+        let idx: Vec<NodeIdx> = (0..len)
+            .map(|_| {
+                nodes.insert(NodeA {
+                    children: bumpalo::vec![in arena;],
+                })
+            })
+            .collect();
+        for &(parent, child) in edges {
+            nodes
+                .get_mut(idx[parent])
+                .unwrap()
+                .children
+                .push(idx[child]);
+        }
+        (Graph::new(nodes), idx)
+    }
+
+    pub struct WeightedNodeA {
+        pub children: Vec<NodeIdx>,
+        pub weights: Vec<f64>,
+    }
+    impl Node for WeightedNodeA {
+        fn children(&self) -> &[NodeIdx] {
+            &self.children
+        }
+    }
+    impl WeightedNode for WeightedNodeA {
+        fn weights(&self) -> &[f64] {
+            &self.weights
+        }
+    }
+
+    /// Build a weighted graph of `len` nodes connected by `(parent, child, weight)` edges
+    pub fn build_weighted_graph(
+        len: usize,
+        edges: &[(usize, usize, f64)],
+    ) -> (Graph<WeightedNodeA>, Vec<NodeIdx>) {
+        let mut nodes = NodeArray::with_key();
+        let idx: Vec<NodeIdx> = (0..len)
+            .map(|_| {
+                nodes.insert(WeightedNodeA {
+                    children: vec![],
+                    weights: vec![],
+                })
+            })
+            .collect();
+        for &(parent, child, weight) in edges {
+            let node = nodes.get_mut(idx[parent]).unwrap();
+            node.children.push(idx[child]);
+            node.weights.push(weight);
+        }
+        (Graph::new(nodes), idx)
+    }
+
+    #[test]
+    fn test_weighted_node() {
+        let (graph, idx) = build_weighted_graph(3, &[(0, 1, 2.5), (0, 2, -1.)]);
+        let node = graph.nodes().get(idx[0]).unwrap();
+        let weighted: Vec<(NodeIdx, f64)> = node.weighted_children().collect();
+        assert_eq!(weighted, [(idx[1], 2.5), (idx[2], -1.)]);
+        assert_eq!(depth_first_search(&graph, &[idx[0]]).len(), 3);
+    }
 
     #[test]
     fn test_bfs() {
@@ -194,7 +779,373 @@ mod tests {
             let _children = params.graph.nodes().get(params.node).unwrap();
             NextMove::VisitChildren
         };
-        breath_first_search(&mut graph, node, &mut visit);
+        let summary = breadth_first_search(&mut graph, node, &mut visit);
+        assert_eq!(
+            summary,
+            SearchSummary {
+                visited: 2,
+                stopped: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_bfs_depth_and_parent() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)]);
+        let mut visited = vec![];
+        breadth_first_search(&mut graph, idx[0], &mut |params| {
+            visited.push((params.node, params.depth, params.parent));
+            if params.depth == 2 {
+                return NextMove::TerminateBranch;
+            }
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            visited,
+            [
+                (idx[0], 0, None),
+                (idx[1], 1, Some(idx[0])),
+                (idx[2], 1, Some(idx[0])),
+                (idx[3], 2, Some(idx[1])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bfs_stop() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3)]);
+        let mut visited = vec![];
+        let summary = breadth_first_search(&mut graph, idx[0], &mut |params| {
+            visited.push(params.node);
+            if params.node == idx[1] {
+                return NextMove::Stop;
+            }
+            NextMove::VisitChildren
+        });
+        assert_eq!(visited, [idx[0], idx[1]]);
+        assert_eq!(
+            summary,
+            SearchSummary {
+                visited: 2,
+                stopped: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_bfs_deadlock() {
+        let arena = Bump::new();
+        // 1 and 2 each wait for the other to be visited
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3)]);
+        let mut visited = vec![];
+        let result = try_breadth_first_search(&mut graph, idx[0], &mut |params| {
+            if params.node == idx[1] && !visited.contains(&idx[2])
+                || params.node == idx[2] && !visited.contains(&idx[1])
+            {
+                return NextMove::Postpone;
+            }
+            visited.push(params.node);
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            result,
+            Err(SearchError::Deadlock(Deadlock {
+                queued: vec![idx[1], idx[2]]
+            }))
+        );
+        assert_eq!(visited, [idx[0]]);
+
+        // Postponing once before the other node resolves is progress, not a deadlock
+        let mut postponed = false;
+        let summary = try_breadth_first_search(&mut graph, idx[0], &mut |params| {
+            if params.node == idx[1] && !postponed {
+                postponed = true;
+                return NextMove::Postpone;
+            }
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            summary,
+            Ok(SearchSummary {
+                visited: 4,
+                stopped: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_dependency_order_diamond() {
+        let arena = Bump::new();
+        let diamonds: [&[(usize, usize)]; 3] = [
+            &[(0, 1), (0, 2), (1, 3), (2, 3)],
+            &[(0, 2), (0, 1), (2, 3), (1, 3)],
+            &[(0, 3), (0, 1), (0, 2), (1, 3), (2, 3)],
+        ];
+        for edges in diamonds {
+            let (graph, idx) = build_graph(&arena, 4, edges);
+            for starts in [vec![idx[0]], vec![idx[1], idx[0]], vec![idx[0], idx[2]]] {
+                let order = dependency_order(&graph, &starts);
+                assert_eq!(order.len(), 4);
+                let position = |i: usize| order.iter().position(|&n| n == idx[i]).unwrap();
+                assert!(position(3) < position(1));
+                assert!(position(3) < position(2));
+                assert!(position(1) < position(0));
+                assert!(position(2) < position(0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_dependency_order_self_loop() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1), (1, 1)]);
+        let err = try_dependency_order(&graph, &[idx[0]]).unwrap_err();
+        assert_eq!(err.cycle, [idx[1]]);
+    }
+
+    #[test]
+    fn test_try_dependency_order_two_node_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (2, 1)]);
+        let err = try_dependency_order(&graph, &[idx[0]]).unwrap_err();
+        assert_eq!(err.cycle, [idx[1], idx[2]]);
+    }
+
+    #[test]
+    fn test_try_dependency_order_cycle_from_one_start() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (2, 3), (3, 4), (4, 2)]);
+        let order = try_dependency_order(&graph, &[idx[0]]).unwrap();
+        assert_eq!(order, [idx[1], idx[0]]);
+        let err = try_dependency_order(&graph, &[idx[0], idx[3]]).unwrap_err();
+        assert_eq!(err.cycle, [idx[3], idx[4], idx[2]]);
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 2), (2, 3)]);
+        let removed = graph.remove_node(idx[2]).unwrap();
+        assert_eq!(&removed.children[..], [idx[3]]);
+        assert!(graph.remove_node(idx[2]).is_none());
+        assert_eq!(depth_first_search(&graph, &[idx[0]]), [idx[0], idx[1]]);
+        assert_eq!(
+            dependency_order(&graph, &[idx[0], idx[3]]),
+            [idx[3], idx[1], idx[0]]
+        );
+    }
+
+    #[test]
+    fn test_remove_nodes() {
+        let arena = Bump::new();
+        let (mut graph, idx) =
+            build_graph(&arena, 5, &[(0, 1), (0, 2), (0, 3), (1, 2), (3, 4), (4, 0)]);
+        graph.remove_nodes(&[idx[2], idx[4], idx[2]]);
+        assert_eq!(graph.nodes().len(), 3);
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(
+            depth_first_search_post_order(&graph, &[idx[0]]),
+            [idx[1], idx[3], idx[0]]
+        );
+    }
+
+    #[test]
+    fn test_retain_nodes() {
+        let arena = Bump::new();
+        let edges: Vec<(usize, usize)> = (0..8)
+            .flat_map(|i| [(i, (i + 1) % 8), (i, (i + 2) % 8), (i, (i + 3) % 8)])
+            .collect();
+        let (mut graph, idx) = build_graph(&arena, 8, &edges);
+        let mut position = 0;
+        graph.retain_nodes(|_, _| {
+            position += 1;
+            position % 2 == 1
+        });
+        assert_eq!(graph.nodes().len(), 4);
+        assert_eq!(graph.validate(), Ok(()));
+        for i in (0..8).step_by(2) {
+            let children = graph.nodes().get(idx[i]).unwrap().children();
+            assert_eq!(children, [idx[(i + 2) % 8]]);
+        }
+    }
+
+    #[test]
+    fn test_retain_nodes_then_traverse() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (0, 3), (3, 2), (2, 4)]);
+        graph.retain_nodes(|node, _| node != idx[1] && node != idx[4]);
+        assert_eq!(
+            try_depth_first_search(&graph, &[idx[0]]),
+            Ok(vec![idx[0], idx[3], idx[2]])
+        );
+        assert_eq!(
+            dependency_order(&graph, &[idx[0]]),
+            [idx[2], idx[3], idx[0]]
+        );
+    }
+
+    #[test]
+    fn test_edges() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 1), (1, 1), (2, 0)]);
+        let edges: Vec<_> = graph.edges().collect();
+        assert_eq!(
+            edges,
+            [
+                (idx[0], idx[1]),
+                (idx[0], idx[1]),
+                (idx[1], idx[1]),
+                (idx[2], idx[0])
+            ]
+        );
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.node_count(), 4);
+    }
+
+    #[test]
+    fn test_counts_after_removal() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 1), (3, 1), (1, 1)]);
+        assert_eq!((graph.node_count(), graph.edge_count()), (4, 5));
+        graph.remove_node(idx[1]);
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 0));
+        let (empty, _) = build_graph(&arena, 0, &[]);
+        assert_eq!((empty.node_count(), empty.edge_count()), (0, 0));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(1);
+        let b = graph.insert_child(a, 2);
+        assert_eq!(graph[a].children, [b]);
+        graph[b].data += 10;
+        assert_eq!(graph[b].data, 12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_missing() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(());
+        graph.remove_node(a);
+        let _ = &graph[a];
+    }
+
+    #[test]
+    fn test_add_remove_edge() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 3, &[(0, 1)]);
+        graph.add_edge(idx[0], idx[2]).unwrap();
+        graph.add_edge(idx[0], idx[1]).unwrap();
+        assert_eq!(
+            graph.nodes().get(idx[0]).unwrap().children(),
+            [idx[1], idx[2], idx[1]]
+        );
+
+        assert!(graph.remove_edge(idx[0], idx[1]));
+        assert_eq!(
+            graph.nodes().get(idx[0]).unwrap().children(),
+            [idx[2], idx[1]]
+        );
+        assert!(!graph.remove_edge(idx[1], idx[0]));
+
+        graph.nodes_mut().remove(idx[2]);
+        assert_eq!(
+            graph.add_edge(idx[0], idx[2]),
+            Err(MissingNodeError {
+                node: idx[2],
+                parent: Some(idx[0])
+            })
+        );
+        assert_eq!(
+            graph.add_edge(idx[2], idx[0]),
+            Err(MissingNodeError {
+                node: idx[2],
+                parent: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_adj_node_edges() {
+        let mut nodes = NodeArray::with_key();
+        let a = nodes.insert(AdjNode::default());
+        let b = nodes.insert(AdjNode::default());
+        let mut graph = Graph::new(nodes);
+        graph.add_edge(a, b).unwrap();
+        graph.add_edge(a, b).unwrap();
+        graph.add_edge(b, a).unwrap();
+        assert!(graph.remove_edge(a, b));
+        assert_eq!(graph.nodes().get(a).unwrap().children, [b]);
+        graph.remove_node(a).unwrap();
+        assert!(graph.nodes().get(b).unwrap().children.is_empty());
+    }
+
+    #[test]
+    fn test_simple_node() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let root = graph.insert_node("root");
+        let a = graph.insert_child(root, "a");
+        let b = graph.insert_child(root, "b");
+        let c = graph.insert_child(a, "c");
+        graph.add_edge(b, c).unwrap();
+        assert_eq!(graph.nodes().get(root).unwrap().children, [a, b]);
+        assert_eq!(graph.nodes().get(c).unwrap().data, "c");
+        assert_eq!(dependency_order(&graph, &[root]), [c, b, a, root]);
+
+        let mut structure: Graph<SimpleNode<()>> = Graph::new(NodeArray::with_key());
+        let parent = structure.insert_node(());
+        structure.insert_child(parent, ());
+        assert_eq!(depth_first_search(&structure, &[parent]).len(), 2);
+    }
+
+    #[test]
+    fn test_try_traversals_missing_node() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (1, 3)]);
+        graph.nodes_mut().remove(idx[2]);
+        let missing = MissingNodeError {
+            node: idx[2],
+            parent: Some(idx[1]),
+        };
+        assert_eq!(try_depth_first_search(&graph, &[idx[0]]), Err(missing));
+        assert_eq!(
+            try_depth_first_search(&graph, &[idx[2]]),
+            Err(MissingNodeError {
+                node: idx[2],
+                parent: None
+            })
+        );
+        assert_eq!(
+            try_depth_first_search_post_order(&graph, &[idx[0]]),
+            Err(missing)
+        );
+        let mut visit = |_: VisitParams<'_, NodeA>| NextMove::VisitChildren;
+        assert_eq!(
+            try_breadth_first_search(&mut graph, idx[0], &mut visit),
+            Err(SearchError::MissingNode(missing))
+        );
+        assert_eq!(
+            try_depth_first_search_with(&mut graph, idx[0], &mut visit),
+            Err(SearchError::MissingNode(missing))
+        );
+        assert_eq!(try_depth_first_search(&graph, &[idx[3]]), Ok(vec![idx[3]]));
+        assert_eq!(
+            try_dependency_order_checked(&graph, &[idx[0]]),
+            Err(DependencyOrderError::MissingNode(missing))
+        );
+        assert_eq!(
+            try_dependency_order_checked(&graph, &[idx[3], idx[1]]),
+            Err(DependencyOrderError::MissingNode(missing))
+        );
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 1), (1, 0)]);
+        assert!(matches!(
+            try_dependency_order_checked(&graph, &[idx[0]]),
+            Err(DependencyOrderError::Cycle(_))
+        ));
     }
 
     #[test]
@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use crate::{checked_dense_ids, Graph, MissingNodeError, Node, NodeIdx, RankDir};
+
+/// Settings of Mermaid output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MermaidOptions {
+    pub direction: RankDir,
+}
+impl Default for MermaidOptions {
+    fn default() -> Self {
+        Self {
+            direction: RankDir::TopBottom,
+        }
+    }
+}
+
+/// Return a Mermaid flowchart declaring every node with its `label` and then every edge
+///
+/// Nodes are named `n0`, `n1`, ... in iteration order since Mermaid ids cannot hold arbitrary
+/// characters. A child that is not in the graph has no name, so it is an error
+pub fn to_mermaid<T: Node>(
+    graph: &Graph<T>,
+    label: impl Fn(NodeIdx, &T) -> String,
+) -> Result<String, MissingNodeError> {
+    to_mermaid_opts(graph, &MermaidOptions::default(), label)
+}
+
+/// Same as [`to_mermaid`] but with `options`
+pub fn to_mermaid_opts<T: Node>(
+    graph: &Graph<T>,
+    options: &MermaidOptions,
+    label: impl Fn(NodeIdx, &T) -> String,
+) -> Result<String, MissingNodeError> {
+    let direction = match options.direction {
+        RankDir::TopBottom => "TD",
+        RankDir::LeftRight => "LR",
+        RankDir::BottomTop => "BT",
+        RankDir::RightLeft => "RL",
+    };
+    let ids = checked_dense_ids(graph)?;
+    let mut out = String::new();
+    writeln!(out, "flowchart {direction}").unwrap();
+    for (i, node) in graph.nodes() {
+        let label = escape(&label(i, node));
+        writeln!(out, "    n{}[\"{label}\"]", ids[i]).unwrap();
+    }
+    for (i, child) in graph.edges() {
+        writeln!(out, "    n{} --> n{}", ids[i], ids[child]).unwrap();
+    }
+    Ok(out)
+}
+
+/// Replace the characters that would end a quoted Mermaid label with entity codes
+///
+/// `#` is replaced too so that user text is never read as an entity code
+fn escape(s: &str) -> String {
+    s.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace(']', "#93;")
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_to_mermaid_diamond() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let name = |i: NodeIdx| idx.iter().position(|&n| n == i).unwrap();
+        let mermaid = to_mermaid(&graph, |i, _| match name(i) {
+            1 => "say \"hi\"".into(),
+            2 => "a[#0]".into(),
+            n => format!("node {n}"),
+        })
+        .unwrap();
+        let expected = [
+            "flowchart TD",
+            "    n0[\"node 0\"]",
+            "    n1[\"say #quot;hi#quot;\"]",
+            "    n2[\"a[#35;0#93;\"]",
+            "    n3[\"node 3\"]",
+            "    n4[\"node 4\"]",
+            "    n0 --> n1",
+            "    n0 --> n2",
+            "    n1 --> n3",
+            "    n2 --> n3",
+            "",
+        ];
+        assert_eq!(mermaid, expected.join("\n"));
+
+        let options = MermaidOptions {
+            direction: RankDir::LeftRight,
+        };
+        let mermaid = to_mermaid_opts(&graph, &options, |_, _| String::new()).unwrap();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+    }
+
+    #[test]
+    fn test_to_mermaid_dangling_child() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 2, &[(0, 1)]);
+        graph.nodes_mut().remove(idx[1]);
+        assert_eq!(
+            to_mermaid(&graph, |_, _| String::new()),
+            Err(MissingNodeError {
+                node: idx[1],
+                parent: Some(idx[0])
+            })
+        );
+    }
+}
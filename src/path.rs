@@ -0,0 +1,1183 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+    ops::Add,
+};
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    ancestors, induced_subgraph, topological_sort, topological_sort_all, CycleError, Graph, Node,
+    NodeIdx, WeightedNode,
+};
+
+/// Return the minimum number of edges from any of `starts` to every reachable node
+///
+/// Starts have distance 0; unreachable nodes are absent from the map
+pub fn bfs_distances<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> SecondaryMap<NodeIdx, usize> {
+    let mut distances = SecondaryMap::new();
+    let mut queue = VecDeque::new();
+    for &start in starts {
+        if distances.contains_key(start) {
+            continue;
+        }
+        distances.insert(start, 0);
+        queue.push_back(start);
+    }
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[node] + 1;
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if distances.contains_key(child) {
+                continue;
+            }
+            distances.insert(child, distance);
+            queue.push_back(child);
+        }
+    }
+    distances
+}
+
+/// Return every node at most `hops` edges away from `center` with its minimum distance
+///
+/// Nodes at the boundary are not expanded, so the rest of the graph is never touched
+pub fn neighborhood<T: Node>(
+    graph: &Graph<T>,
+    center: NodeIdx,
+    hops: usize,
+) -> SecondaryMap<NodeIdx, usize> {
+    let mut distances = SecondaryMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(center, 0);
+    queue.push_back(center);
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[node];
+        if distance == hops {
+            continue;
+        }
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if distances.contains_key(child) {
+                continue;
+            }
+            distances.insert(child, distance + 1);
+            queue.push_back(child);
+        }
+    }
+    distances
+}
+
+/// Return for every reachable node the start that reaches it first and its distance from it
+///
+/// When several starts are equally close, the one appearing earliest in `starts` wins
+pub fn multi_source_bfs<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> SecondaryMap<NodeIdx, (NodeIdx, usize)> {
+    let mut sources = SecondaryMap::new();
+    let mut queue = VecDeque::new();
+    for &start in starts {
+        if sources.contains_key(start) {
+            continue;
+        }
+        sources.insert(start, (start, 0));
+        queue.push_back(start);
+    }
+    // Within one distance, nodes of earlier starts are queued first, which settles ties
+    while let Some(node) = queue.pop_front() {
+        let (source, distance) = sources[node];
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if sources.contains_key(child) {
+                continue;
+            }
+            sources.insert(child, (source, distance + 1));
+            queue.push_back(child);
+        }
+    }
+    sources
+}
+
+/// Return a path with the fewest edges from `start` to `target` by running depth-limited searches
+/// with a growing limit, up to `max_depth` edges
+///
+/// Memory stays proportional to the depth rather than to the width of the graph
+pub fn iterative_deepening<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+    target: NodeIdx,
+    max_depth: usize,
+) -> Option<Vec<NodeIdx>> {
+    (0..=max_depth).find_map(|limit| depth_limited_path(graph, start, target, limit))
+}
+
+/// Return a path of at most `limit` edges from `start` to `target` without repeating nodes
+fn depth_limited_path<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+    target: NodeIdx,
+    limit: usize,
+) -> Option<Vec<NodeIdx>> {
+    let mut on_path = SecondaryMap::new();
+    on_path.insert(start, ());
+    let mut stack: Vec<(NodeIdx, usize)> = vec![(start, 0)];
+    while let Some((node, next_child)) = stack.last_mut() {
+        let node = *node;
+        if node == target {
+            return Some(stack.iter().map(|&(n, _)| n).collect());
+        }
+        let children = graph.nodes().get(node).unwrap().children();
+        let child = children.get(*next_child).copied();
+        *next_child += 1;
+        match child {
+            Some(child) if stack.len() <= limit && !on_path.contains_key(child) => {
+                on_path.insert(child, ());
+                stack.push((child, 0));
+            }
+            Some(_) if stack.len() <= limit => (),
+            _ => {
+                stack.pop();
+                on_path.remove(node);
+            }
+        }
+    }
+    None
+}
+
+/// Return the node each reachable node was discovered from in a breadth-first search
+///
+/// Following the map back from any node gives a shortest path to `start`, which itself is absent
+pub fn bfs_predecessors<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+) -> SecondaryMap<NodeIdx, NodeIdx> {
+    let mut predecessors = SecondaryMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if child == start || predecessors.contains_key(child) {
+                continue;
+            }
+            predecessors.insert(child, node);
+            queue.push_back(child);
+        }
+    }
+    predecessors
+}
+
+/// Return a path with the fewest edges from `from` to `to`, both included
+pub fn shortest_path<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+    to: NodeIdx,
+) -> Option<Vec<NodeIdx>> {
+    if from == to {
+        return Some(vec![from]);
+    }
+    let predecessors = bfs_predecessors(graph, from);
+    predecessors.get(to)?;
+    let mut path = vec![to];
+    let mut node = to;
+    while let Some(&predecessor) = predecessors.get(node) {
+        path.push(predecessor);
+        node = predecessor;
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Return every path from `from` to `to` without repeated nodes, of at most `max_len` edges if
+/// given
+///
+/// Paths come in depth-first order following the order of children. Parallel edges yield the
+/// same path once per edge. If `from == to`, the paths are the simple cycles through `from`
+pub fn all_simple_paths<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+    to: NodeIdx,
+    max_len: Option<usize>,
+) -> Vec<Vec<NodeIdx>> {
+    all_simple_paths_iter(graph, from, to, max_len).collect()
+}
+
+/// Same as [`all_simple_paths`] but yield the paths lazily
+pub fn all_simple_paths_iter<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+    to: NodeIdx,
+    max_len: Option<usize>,
+) -> AllSimplePaths<'_, T> {
+    let mut on_path = SecondaryMap::new();
+    on_path.insert(from, ());
+    AllSimplePaths {
+        graph,
+        to,
+        max_len: max_len.unwrap_or(usize::MAX),
+        on_path,
+        stack: vec![(from, 0)],
+    }
+}
+
+/// Lazy enumeration of simple paths returned by [`all_simple_paths_iter`]
+#[derive(Debug, Clone)]
+pub struct AllSimplePaths<'a, T> {
+    graph: &'a Graph<T>,
+    to: NodeIdx,
+    max_len: usize,
+    on_path: SecondaryMap<NodeIdx, ()>,
+    /// Nodes of the current path with the index of their next child to try
+    stack: Vec<(NodeIdx, usize)>,
+}
+impl<T: Node> Iterator for AllSimplePaths<'_, T> {
+    type Item = Vec<NodeIdx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, next_child)) = self.stack.last_mut() {
+            let node = *node;
+            let children = self.graph.nodes().get(node).unwrap().children();
+            let child = children.get(*next_child).copied();
+            *next_child += 1;
+            let len = self.stack.len();
+            match child {
+                Some(child) if len <= self.max_len && child == self.to => {
+                    let mut path: Vec<NodeIdx> = self.stack.iter().map(|&(n, _)| n).collect();
+                    path.push(child);
+                    return Some(path);
+                }
+                Some(child) if len < self.max_len && !self.on_path.contains_key(child) => {
+                    self.on_path.insert(child, ());
+                    self.stack.push((child, 0));
+                }
+                Some(_) if len <= self.max_len => (),
+                _ => {
+                    self.stack.pop();
+                    self.on_path.remove(node);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Return the cost of the cheapest path from `start` to every reachable node
+///
+/// # Panics
+///
+/// Panics on a negative weight; see [`bellman_ford`] for those
+pub fn dijkstra<N: WeightedNode>(graph: &Graph<N>, start: NodeIdx) -> SecondaryMap<NodeIdx, f64> {
+    dijkstra_search(graph, start, None, |_, node, i| node.weights()[i])
+        .unwrap_or_else(|e| panic!("{e}"))
+        .0
+}
+
+/// Return the cost and the nodes of the cheapest path from `from` to `to`, both included
+///
+/// # Panics
+///
+/// Panics on a negative weight
+pub fn dijkstra_path<N: WeightedNode>(
+    graph: &Graph<N>,
+    from: NodeIdx,
+    to: NodeIdx,
+) -> Option<(f64, Vec<NodeIdx>)> {
+    let search = dijkstra_search(graph, from, Some(to), |_, node, i| node.weights()[i]);
+    let (costs, predecessors) = search.unwrap_or_else(|e| panic!("{e}"));
+    cheapest_path(&costs, &predecessors, to)
+}
+
+/// Same as [`dijkstra`] but `weight` gives the weight of the edge from a parent to a child with
+/// the payload of the parent
+///
+/// Parallel edges get the same weight
+///
+/// # Panics
+///
+/// Panics on a negative weight; see [`try_dijkstra_by`]
+pub fn dijkstra_by<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+    weight: impl Fn(NodeIdx, NodeIdx, &T) -> f64,
+) -> SecondaryMap<NodeIdx, f64> {
+    try_dijkstra_by(graph, start, weight).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`dijkstra_by`] but return an error instead of panicking on a negative weight
+pub fn try_dijkstra_by<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+    weight: impl Fn(NodeIdx, NodeIdx, &T) -> f64,
+) -> Result<SecondaryMap<NodeIdx, f64>, NegativeWeight> {
+    let weight = |parent, node: &T, i: usize| weight(parent, node.children()[i], node);
+    Ok(dijkstra_search(graph, start, None, weight)?.0)
+}
+
+/// Same as [`dijkstra_path`] with the weights of [`dijkstra_by`]
+///
+/// # Panics
+///
+/// Panics on a negative weight
+pub fn dijkstra_path_by<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+    to: NodeIdx,
+    weight: impl Fn(NodeIdx, NodeIdx, &T) -> f64,
+) -> Option<(f64, Vec<NodeIdx>)> {
+    let weight = |parent, node: &T, i: usize| weight(parent, node.children()[i], node);
+    let search = dijkstra_search(graph, from, Some(to), weight);
+    let (costs, predecessors) = search.unwrap_or_else(|e| panic!("{e}"));
+    cheapest_path(&costs, &predecessors, to)
+}
+
+/// An edge weight is negative, which Dijkstra's algorithm cannot handle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NegativeWeight {
+    pub parent: NodeIdx,
+    pub child: NodeIdx,
+    pub weight: f64,
+}
+impl fmt::Display for NegativeWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "negative edge weight {} from {:?} to {:?}",
+            self.weight, self.parent, self.child
+        )
+    }
+}
+impl std::error::Error for NegativeWeight {}
+
+fn cheapest_path(
+    costs: &SecondaryMap<NodeIdx, f64>,
+    predecessors: &SecondaryMap<NodeIdx, NodeIdx>,
+    to: NodeIdx,
+) -> Option<(f64, Vec<NodeIdx>)> {
+    let &cost = costs.get(to)?;
+    let mut path = vec![to];
+    let mut node = to;
+    while let Some(&predecessor) = predecessors.get(node) {
+        path.push(predecessor);
+        node = predecessor;
+    }
+    path.reverse();
+    Some((cost, path))
+}
+
+/// Heap entry ordered so that [`BinaryHeap`] pops the lowest cost first
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    cost: f64,
+    node: NodeIdx,
+}
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Candidate {}
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Final costs and the predecessors on the cheapest paths
+type CheapestPaths = (SecondaryMap<NodeIdx, f64>, SecondaryMap<NodeIdx, NodeIdx>);
+
+/// Return the [`CheapestPaths`] from `start`, stopping early at `goal`
+///
+/// `weight` gives the weight of the edge from a node to its child at an index
+fn dijkstra_search<T: Node>(
+    graph: &Graph<T>,
+    start: NodeIdx,
+    goal: Option<NodeIdx>,
+    weight: impl Fn(NodeIdx, &T, usize) -> f64,
+) -> Result<CheapestPaths, NegativeWeight> {
+    let mut tentative = SecondaryMap::new();
+    let mut costs = SecondaryMap::new();
+    let mut predecessors = SecondaryMap::new();
+    let mut heap = BinaryHeap::new();
+    tentative.insert(start, 0.);
+    heap.push(Candidate {
+        cost: 0.,
+        node: start,
+    });
+    while let Some(Candidate { cost, node }) = heap.pop() {
+        if costs.contains_key(node) {
+            continue;
+        }
+        costs.insert(node, cost);
+        if Some(node) == goal {
+            break;
+        }
+        let value = graph.nodes().get(node).unwrap();
+        for (i, &child) in value.children().iter().enumerate() {
+            let weight = weight(node, value, i);
+            if weight < 0. {
+                return Err(NegativeWeight {
+                    parent: node,
+                    child,
+                    weight,
+                });
+            }
+            let cost = cost + weight;
+            if costs.contains_key(child) || tentative.get(child).is_some_and(|&c| c <= cost) {
+                continue;
+            }
+            tentative.insert(child, cost);
+            predecessors.insert(child, node);
+            heap.push(Candidate { cost, node: child });
+        }
+    }
+    Ok((costs, predecessors))
+}
+
+/// A cycle of negative total weight is reachable, so some costs are unbounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegativeCycle {
+    /// A node on the cycle
+    pub node: NodeIdx,
+}
+impl fmt::Display for NegativeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "negative cycle through {:?}", self.node)
+    }
+}
+impl std::error::Error for NegativeCycle {}
+
+/// Return the cost of the cheapest path from `start` to every reachable node
+///
+/// Unlike [`dijkstra`], negative weights are allowed as long as no negative cycle is reachable
+pub fn bellman_ford<N: WeightedNode>(
+    graph: &Graph<N>,
+    start: NodeIdx,
+) -> Result<SecondaryMap<NodeIdx, f64>, NegativeCycle> {
+    let reachable: Vec<NodeIdx> = graph.bfs(start).collect();
+    let mut costs = SecondaryMap::new();
+    let mut predecessors = SecondaryMap::new();
+    costs.insert(start, 0.);
+    let relax = |costs: &mut SecondaryMap<NodeIdx, f64>,
+                 predecessors: &mut SecondaryMap<NodeIdx, NodeIdx>|
+     -> Option<NodeIdx> {
+        let mut relaxed = None;
+        for &node in &reachable {
+            let Some(&cost) = costs.get(node) else {
+                continue;
+            };
+            for (child, weight) in graph.nodes().get(node).unwrap().weighted_children() {
+                let cost = cost + weight;
+                if costs.get(child).is_some_and(|&c| c <= cost) {
+                    continue;
+                }
+                costs.insert(child, cost);
+                predecessors.insert(child, node);
+                relaxed = Some(child);
+            }
+        }
+        relaxed
+    };
+    for _ in 1..reachable.len() {
+        if relax(&mut costs, &mut predecessors).is_none() {
+            return Ok(costs);
+        }
+    }
+    let Some(mut node) = relax(&mut costs, &mut predecessors) else {
+        return Ok(costs);
+    };
+    // The relaxed node might only hang off the cycle; walking back far enough lands on it
+    for _ in 0..reachable.len() {
+        node = predecessors[node];
+    }
+    Err(NegativeCycle { node })
+}
+
+/// Return the total weight and the nodes of the heaviest path of the graph
+///
+/// Negative weights are allowed, so the path may end before a leaf. An empty graph gives an empty
+/// path; ties go to the shorter path, then to the path found first in topological order
+pub fn longest_path<N: WeightedNode>(graph: &Graph<N>) -> Result<(f64, Vec<NodeIdx>), CycleError> {
+    longest(graph, |node, i| node.weights()[i])
+}
+
+/// Same as [`longest_path`] with every edge weighing 1, so the length is the number of edges
+pub fn longest_path_unweighted<T: Node>(
+    graph: &Graph<T>,
+) -> Result<(usize, Vec<NodeIdx>), CycleError> {
+    longest(graph, |_, _| 1)
+}
+
+/// Solve the longest path by dynamic programming over the topological order, `weight` giving the
+/// weight of the edge to the child at an index
+fn longest<T: Node, W: Copy + Default + PartialOrd + Add<Output = W>>(
+    graph: &Graph<T>,
+    weight: impl Fn(&T, usize) -> W,
+) -> Result<(W, Vec<NodeIdx>), CycleError> {
+    let order = topological_sort_all(graph)?;
+    let mut best: SecondaryMap<NodeIdx, (W, Option<NodeIdx>)> = SecondaryMap::new();
+    for &node in &order {
+        let value = graph.nodes().get(node).unwrap();
+        // Stopping at this node is a candidate too, so a path never takes a negative tail
+        let mut node_best = (W::default(), None);
+        for (i, &child) in value.children().iter().enumerate() {
+            let length = weight(value, i) + best[child].0;
+            if node_best.0 < length {
+                node_best = (length, Some(child));
+            }
+        }
+        best.insert(node, node_best);
+    }
+    let Some(mut node) = order
+        .iter()
+        .copied()
+        .reduce(|a, b| if best[a].0 < best[b].0 { b } else { a })
+    else {
+        return Ok((W::default(), vec![]));
+    };
+    let length = best[node].0;
+    let mut path = vec![node];
+    while let Some(next) = best[node].1 {
+        path.push(next);
+        node = next;
+    }
+    Ok((length, path))
+}
+
+/// Return the heaviest path from `starts` by node weight along with its total weight
+///
+/// Edges point from a task to the tasks it depends on, so the path starts at the task finishing
+/// last. Weights may be zero or negative; on ties the path of the first node in topological order
+/// wins
+pub fn critical_path<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    weight: impl Fn(NodeIdx, &T) -> f64,
+) -> Result<(f64, Vec<NodeIdx>), CycleError> {
+    let (order, completion) = completion_times(graph, starts, weight)?;
+    let Some(mut node) = order.iter().copied().reduce(|a, b| {
+        if completion[a].0 < completion[b].0 {
+            b
+        } else {
+            a
+        }
+    }) else {
+        return Ok((0., vec![]));
+    };
+    let total = completion[node].0;
+    let mut path = vec![node];
+    while let Some(next) = completion[node].1 {
+        path.push(next);
+        node = next;
+    }
+    Ok((total, path))
+}
+
+/// Return for every node reachable from `starts` the earliest time it can complete if it starts
+/// once all its children are complete
+///
+/// The weight of a node is its duration; the largest of these times is the total weight of the
+/// [`critical_path`]
+pub fn earliest_completion_times<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    weight: impl Fn(NodeIdx, &T) -> f64,
+) -> Result<SecondaryMap<NodeIdx, f64>, CycleError> {
+    let (_, completion) = completion_times(graph, starts, weight)?;
+    Ok(completion
+        .into_iter()
+        .map(|(n, (time, _))| (n, time))
+        .collect())
+}
+
+/// Completion time of every node and the child it waits for last
+type Completion = SecondaryMap<NodeIdx, (f64, Option<NodeIdx>)>;
+
+/// Return the topological order along with the [`Completion`] of every node
+fn completion_times<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    weight: impl Fn(NodeIdx, &T) -> f64,
+) -> Result<(Vec<NodeIdx>, Completion), CycleError> {
+    let order = topological_sort(graph, starts)?;
+    let mut completion = Completion::new();
+    for &node in &order {
+        let value = graph.nodes().get(node).unwrap();
+        let mut last: Option<NodeIdx> = None;
+        for &child in value.children() {
+            if last.is_none_or(|last| completion[last].0 < completion[child].0) {
+                last = Some(child);
+            }
+        }
+        let start = last.map_or(0., |last| completion[last].0);
+        completion.insert(node, (start + weight(node, value), last));
+    }
+    Ok((order, completion))
+}
+
+/// [`count_paths`] cannot give a finite count that fits in a `u128`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountPathsError {
+    /// A cycle lies on the counted paths, so there are infinitely many
+    Cycle(CycleError),
+    /// The count of paths to this node overflows
+    Overflow { node: NodeIdx },
+}
+impl fmt::Display for CountPathsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(e) => e.fmt(f),
+            Self::Overflow { node } => write!(f, "path count to {node:?} overflows"),
+        }
+    }
+}
+impl std::error::Error for CountPathsError {}
+impl From<CycleError> for CountPathsError {
+    fn from(e: CycleError) -> Self {
+        Self::Cycle(e)
+    }
+}
+
+/// Return the number of distinct paths from `from` to `to`
+///
+/// Parallel edges count as distinct paths and `from == to` counts the empty path once. Only
+/// cycles on some path from `from` to `to` are an error
+pub fn count_paths<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+    to: NodeIdx,
+) -> Result<u128, CountPathsError> {
+    if !crate::is_reachable(graph, from, to) {
+        return Ok(0);
+    }
+    let mut upstream = ancestors(graph, to);
+    upstream.insert(to, ());
+    let between: Vec<NodeIdx> = graph
+        .dfs(from)
+        .filter(|&n| upstream.contains_key(n))
+        .collect();
+    let (subgraph, mapping) = induced_subgraph(graph, &between);
+    let original: SecondaryMap<NodeIdx, NodeIdx> =
+        mapping.iter().map(|(node, &new)| (new, node)).collect();
+    match count_paths_to_all(&subgraph, mapping[from]) {
+        Ok(counts) => Ok(counts[mapping[to]]),
+        Err(CountPathsError::Cycle(e)) => Err(CountPathsError::Cycle(CycleError {
+            cycle: e.cycle.iter().map(|&n| original[n]).collect(),
+        })),
+        Err(CountPathsError::Overflow { node }) => Err(CountPathsError::Overflow {
+            node: original[node],
+        }),
+    }
+}
+
+/// Return the number of distinct paths from `from` to every node reachable from it
+///
+/// Unlike [`count_paths`], any cycle reachable from `from` is an error
+pub fn count_paths_to_all<T: Node>(
+    graph: &Graph<T>,
+    from: NodeIdx,
+) -> Result<SecondaryMap<NodeIdx, u128>, CountPathsError> {
+    let order = topological_sort(graph, &[from])?;
+    let mut counts: SecondaryMap<NodeIdx, u128> = order.iter().map(|&n| (n, 0)).collect();
+    counts[from] = 1;
+    // Parents come last in the topological order, so every count is final before it is passed on
+    for &node in order.iter().rev() {
+        let count = counts[node];
+        for &child in graph.nodes().get(node).unwrap().children() {
+            counts[child] = counts[child]
+                .checked_add(count)
+                .ok_or(CountPathsError::Overflow { node: child })?;
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use bumpalo::Bump;
+
+    use crate::{
+        tests::{build_graph, build_weighted_graph},
+        NodeArray,
+    };
+
+    use super::*;
+
+    /// 3x3 grid where every cell links to the cell below and the cell to the right
+    fn grid_edges() -> Vec<(usize, usize)> {
+        let mut edges = vec![];
+        for row in 0..3 {
+            for col in 0..3 {
+                let cell = row * 3 + col;
+                if row < 2 {
+                    edges.push((cell, cell + 3));
+                }
+                if col < 2 {
+                    edges.push((cell, cell + 1));
+                }
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn test_bfs_distances() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 9, &grid_edges());
+        let distances = bfs_distances(&graph, &[idx[0]]);
+        let expected = [0, 1, 2, 1, 2, 3, 2, 3, 4];
+        for (cell, &distance) in expected.iter().enumerate() {
+            assert_eq!(distances[idx[cell]], distance);
+        }
+
+        let distances = bfs_distances(&graph, &[idx[4], idx[1]]);
+        assert!(!distances.contains_key(idx[0]));
+        assert!(!distances.contains_key(idx[3]));
+        assert!(!distances.contains_key(idx[6]));
+        let expected = [(1, 0), (2, 1), (4, 0), (5, 1), (7, 1), (8, 2)];
+        for (cell, distance) in expected {
+            assert_eq!(distances[idx[cell]], distance);
+        }
+    }
+
+    #[test]
+    fn test_bfs_distances_single_start() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (3, 0)]);
+        let distances = bfs_distances(&graph, &[idx[0]]);
+        assert_eq!(distances.len(), 3);
+        assert_eq!(distances[idx[0]], 0);
+        assert_eq!(distances[idx[1]], 1);
+        assert_eq!(distances[idx[2]], 2);
+    }
+
+    /// Records whether its children were ever asked for
+    struct TouchedNode {
+        children: Vec<NodeIdx>,
+        touched: Cell<bool>,
+    }
+    impl Node for TouchedNode {
+        fn children(&self) -> &[NodeIdx] {
+            self.touched.set(true);
+            &self.children
+        }
+    }
+
+    fn touched_chain(len: usize) -> (Graph<TouchedNode>, Vec<NodeIdx>) {
+        let mut nodes = NodeArray::with_key();
+        let idx: Vec<NodeIdx> = (0..len)
+            .map(|_| {
+                nodes.insert(TouchedNode {
+                    children: vec![],
+                    touched: Cell::new(false),
+                })
+            })
+            .collect();
+        for pair in idx.windows(2) {
+            nodes[pair[0]].children.push(pair[1]);
+        }
+        (Graph::new(nodes), idx)
+    }
+
+    #[test]
+    fn test_neighborhood_chain() {
+        let (graph, idx) = touched_chain(100);
+        let near = neighborhood(&graph, idx[10], 3);
+        assert_eq!(near.len(), 4);
+        for hop in 0..=3 {
+            assert_eq!(near[idx[10 + hop]], hop);
+        }
+        let touched = |i: usize| graph[idx[i]].touched.get();
+        assert!((10..13).all(touched));
+        assert!(!(0..10).any(touched));
+        assert!(!(13..100).any(touched));
+
+        assert_eq!(neighborhood(&graph, idx[99], 3).len(), 1);
+        assert_eq!(neighborhood(&graph, idx[0], 0).len(), 1);
+    }
+
+    #[test]
+    fn test_depth_first_search_limited_chain() {
+        let (graph, idx) = touched_chain(100);
+        let visit = crate::depth_first_search_limited(&graph, &[idx[0]], 3);
+        assert_eq!(visit, idx[..4]);
+        let touched = |i: usize| graph[idx[i]].touched.get();
+        assert!((0..3).all(touched));
+        assert!(!(3..100).any(touched));
+    }
+
+    #[test]
+    fn test_multi_source_bfs() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 2 -> 3 <- 4 <- 5, and 0 -> 6
+        let (graph, idx) =
+            build_graph(&arena, 7, &[(0, 1), (1, 2), (2, 3), (5, 4), (4, 3), (0, 6)]);
+        let sources = multi_source_bfs(&graph, &[idx[5], idx[0]]);
+        assert_eq!(sources[idx[0]], (idx[0], 0));
+        assert_eq!(sources[idx[1]], (idx[0], 1));
+        assert_eq!(sources[idx[4]], (idx[5], 1));
+        assert_eq!(sources[idx[6]], (idx[0], 1));
+        // Distance 3 from 0 and 2 from 5
+        assert_eq!(sources[idx[3]], (idx[5], 2));
+
+        // Distance 2 from both 1 and 5; the earlier start wins
+        let sources = multi_source_bfs(&graph, &[idx[1], idx[5]]);
+        assert_eq!(sources[idx[3]], (idx[1], 2));
+        let sources = multi_source_bfs(&graph, &[idx[5], idx[1]]);
+        assert_eq!(sources[idx[3]], (idx[5], 2));
+    }
+
+    #[test]
+    fn test_iterative_deepening() {
+        let arena = Bump::new();
+        let (graph, idx) =
+            build_graph(&arena, 6, &[(0, 1), (1, 2), (2, 3), (0, 4), (4, 3), (3, 0)]);
+        assert_eq!(
+            iterative_deepening(&graph, idx[0], idx[3], 5),
+            Some(vec![idx[0], idx[4], idx[3]])
+        );
+        assert_eq!(iterative_deepening(&graph, idx[0], idx[3], 1), None);
+        assert_eq!(
+            iterative_deepening(&graph, idx[1], idx[1], 0),
+            Some(vec![idx[1]])
+        );
+        assert_eq!(iterative_deepening(&graph, idx[0], idx[5], 10), None);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 6, &[(0, 1), (0, 4), (1, 2), (2, 3), (4, 3)]);
+        assert_eq!(
+            shortest_path(&graph, idx[0], idx[3]),
+            Some(vec![idx[0], idx[4], idx[3]])
+        );
+        assert_eq!(shortest_path(&graph, idx[2], idx[2]), Some(vec![idx[2]]));
+        assert_eq!(shortest_path(&graph, idx[0], idx[5]), None);
+        assert_eq!(shortest_path(&graph, idx[3], idx[0]), None);
+    }
+
+    #[test]
+    fn test_shortest_path_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (2, 3), (0, 2)]);
+        assert_eq!(
+            shortest_path(&graph, idx[1], idx[0]),
+            Some(vec![idx[1], idx[2], idx[0]])
+        );
+        assert_eq!(
+            shortest_path(&graph, idx[0], idx[3]),
+            Some(vec![idx[0], idx[2], idx[3]])
+        );
+    }
+
+    #[test]
+    fn test_all_simple_paths() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 0 -> 3, 1 -> 2, 3 -> 0
+        let edges = [(0, 1), (0, 2), (0, 3), (1, 3), (2, 3), (1, 2), (3, 0)];
+        let (graph, idx) = build_graph(&arena, 5, &edges);
+        let mut paths = all_simple_paths(&graph, idx[0], idx[3], None);
+        paths.sort();
+        let mut expected = vec![
+            vec![idx[0], idx[1], idx[3]],
+            vec![idx[0], idx[2], idx[3]],
+            vec![idx[0], idx[3]],
+            vec![idx[0], idx[1], idx[2], idx[3]],
+        ];
+        expected.sort();
+        assert_eq!(paths, expected);
+
+        let mut paths = all_simple_paths(&graph, idx[0], idx[3], Some(2));
+        paths.sort();
+        expected.retain(|path| path.len() <= 3);
+        assert_eq!(paths, expected);
+        assert_eq!(
+            all_simple_paths(&graph, idx[0], idx[3], Some(1)),
+            [vec![idx[0], idx[3]]]
+        );
+        assert!(all_simple_paths(&graph, idx[0], idx[3], Some(0)).is_empty());
+        assert!(all_simple_paths(&graph, idx[0], idx[4], None).is_empty());
+        assert_eq!(
+            all_simple_paths(&graph, idx[2], idx[2], None),
+            [
+                vec![idx[2], idx[3], idx[0], idx[1], idx[2]],
+                vec![idx[2], idx[3], idx[0], idx[2]]
+            ]
+        );
+        assert!(all_simple_paths(&graph, idx[4], idx[4], None).is_empty());
+    }
+
+    #[test]
+    fn test_all_simple_paths_diamond() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(
+            all_simple_paths(&graph, idx[0], idx[3], None),
+            [vec![idx[0], idx[1], idx[3]], vec![idx[0], idx[2], idx[3]]]
+        );
+        let mut paths = all_simple_paths_iter(&graph, idx[0], idx[3], None);
+        assert_eq!(paths.next(), Some(vec![idx[0], idx[1], idx[3]]));
+    }
+
+    #[test]
+    fn test_all_simple_paths_cycles() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 2 -> 0, 1 -> 0, 2 -> 2, 2 -> 3
+        let edges = [(0, 1), (1, 2), (2, 0), (1, 0), (2, 2), (2, 3)];
+        let (graph, idx) = build_graph(&arena, 4, &edges);
+        assert_eq!(
+            all_simple_paths(&graph, idx[0], idx[3], None),
+            [vec![idx[0], idx[1], idx[2], idx[3]]]
+        );
+        assert_eq!(
+            all_simple_paths(&graph, idx[0], idx[0], None),
+            [
+                vec![idx[0], idx[1], idx[2], idx[0]],
+                vec![idx[0], idx[1], idx[0]]
+            ]
+        );
+        assert_eq!(
+            all_simple_paths(&graph, idx[0], idx[0], Some(2)),
+            [vec![idx[0], idx[1], idx[0]]]
+        );
+        assert_eq!(
+            all_simple_paths(&graph, idx[2], idx[2], None),
+            [vec![idx[2], idx[0], idx[1], idx[2]], vec![idx[2], idx[2]]]
+        );
+        assert!(all_simple_paths(&graph, idx[3], idx[3], None).is_empty());
+    }
+
+    /// Nodes `0..len` where every node links to the next two, so `F(i + 1)` paths lead from 0 to
+    /// `i`
+    fn fibonacci_edges(len: usize) -> Vec<(usize, usize)> {
+        let mut edges = vec![];
+        for i in 0..len {
+            edges.extend(
+                [i + 1, i + 2]
+                    .into_iter()
+                    .filter(|&j| j < len)
+                    .map(|j| (i, j)),
+            );
+        }
+        edges
+    }
+
+    #[test]
+    fn test_count_paths_ladder() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 186, &fibonacci_edges(186));
+        assert_eq!(count_paths(&graph, idx[0], idx[10]), Ok(89));
+        assert_eq!(
+            count_paths(&graph, idx[0], idx[185]),
+            Ok(332825110087067562321196029789634457848)
+        );
+        assert_eq!(count_paths(&graph, idx[3], idx[3]), Ok(1));
+        assert_eq!(count_paths(&graph, idx[10], idx[0]), Ok(0));
+        let counts = count_paths_to_all(&graph, idx[0]).unwrap();
+        assert_eq!(counts.len(), 186);
+        assert_eq!(counts[idx[5]], 8);
+
+        let (graph, idx) = build_graph(&arena, 187, &fibonacci_edges(187));
+        assert_eq!(
+            count_paths(&graph, idx[0], idx[186]),
+            Err(CountPathsError::Overflow { node: idx[186] })
+        );
+    }
+
+    #[test]
+    fn test_count_paths_cycle() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 3 -> 4 and 0 -> 3 with a cycle 1 <-> 2
+        let edges = [(0, 1), (1, 3), (0, 3), (1, 2), (2, 1), (3, 4), (0, 1)];
+        let (graph, idx) = build_graph(&arena, 5, &edges);
+        let err = count_paths(&graph, idx[0], idx[3]).unwrap_err();
+        let CountPathsError::Cycle(CycleError { mut cycle }) = err else {
+            panic!("{err}");
+        };
+        cycle.sort();
+        assert_eq!(cycle, [idx[1], idx[2]]);
+        assert!(count_paths(&graph, idx[2], idx[4]).is_err());
+        assert_eq!(count_paths(&graph, idx[3], idx[4]), Ok(1));
+        assert!(count_paths_to_all(&graph, idx[0]).is_err());
+
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 2), (2, 3), (3, 3)]);
+        assert_eq!(count_paths(&graph, idx[0], idx[2]), Ok(2));
+        assert!(count_paths_to_all(&graph, idx[0]).is_err());
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let arena = Bump::new();
+        // deploy -> build, deploy -> test -> build, build -> fetch, build -> codegen, and a
+        // milestone of no duration depending on deploy
+        let edges = [(0, 1), (0, 2), (2, 1), (1, 3), (1, 4), (5, 0)];
+        let (graph, idx) = build_graph(&arena, 6, &edges);
+        let durations = [1., 5., 3., 2., 4., 0.];
+        let weight = |node, _: &_| durations[idx.iter().position(|&n| n == node).unwrap()];
+
+        let times = earliest_completion_times(&graph, &[idx[0]], weight).unwrap();
+        let expected = [13., 9., 12., 2., 4.];
+        assert_eq!(times.len(), 5);
+        for (i, expected) in expected.into_iter().enumerate() {
+            assert_eq!(times[idx[i]], expected);
+        }
+        assert_eq!(
+            critical_path(&graph, &[idx[0]], weight),
+            Ok((13., vec![idx[0], idx[2], idx[1], idx[4]]))
+        );
+        assert_eq!(
+            critical_path(&graph, &[idx[3], idx[5]], weight),
+            Ok((13., vec![idx[0], idx[2], idx[1], idx[4]]))
+        );
+        assert_eq!(critical_path(&graph, &[], weight), Ok((0., vec![])));
+
+        let negative = |node, _: &_| if node == idx[4] { -10. } else { 1. };
+        assert_eq!(
+            critical_path(&graph, &[idx[1]], negative),
+            Ok((2., vec![idx[1], idx[3]]))
+        );
+
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (2, 1)]);
+        assert!(critical_path(&graph, &[idx[0]], |_, _| 1.).is_err());
+    }
+
+    #[test]
+    fn test_longest_path() {
+        let (graph, idx) = build_weighted_graph(
+            6,
+            &[
+                (0, 1, 3.),
+                (0, 2, 1.),
+                (1, 3, 1.),
+                (2, 3, 5.),
+                (3, 4, 2.),
+                (0, 4, 1.),
+            ],
+        );
+        assert_eq!(
+            longest_path(&graph).unwrap(),
+            (8., vec![idx[0], idx[2], idx[3], idx[4]])
+        );
+        let (length, path) = longest_path_unweighted(&graph).unwrap();
+        assert_eq!(length, 3);
+        assert_eq!(path.len(), 4);
+        assert_eq!((path[0], path[3]), (idx[0], idx[4]));
+
+        let (graph, idx) = build_weighted_graph(2, &[]);
+        let (length, path) = longest_path(&graph).unwrap();
+        assert_eq!(length, 0.);
+        assert!(path == [idx[0]] || path == [idx[1]]);
+        let (graph, _) = build_weighted_graph(0, &[]);
+        assert_eq!(longest_path(&graph).unwrap(), (0., vec![]));
+        let (graph, _) = build_weighted_graph(2, &[(0, 1, 1.), (1, 0, 1.)]);
+        assert_eq!(longest_path(&graph).unwrap_err().cycle.len(), 2);
+    }
+
+    #[test]
+    fn test_longest_path_negative_edge() {
+        let (graph, idx) = build_weighted_graph(3, &[(0, 1, 10.), (1, 2, -5.)]);
+        assert_eq!(longest_path(&graph).unwrap(), (10., vec![idx[0], idx[1]]));
+
+        let (graph, _) = build_weighted_graph(3, &[(0, 1, -1.), (1, 2, -2.)]);
+        let (length, path) = longest_path(&graph).unwrap();
+        assert_eq!(length, 0.);
+        assert_eq!(path.len(), 1);
+
+        let (graph, idx) = build_weighted_graph(3, &[(0, 1, -1.), (1, 2, 4.)]);
+        assert_eq!(longest_path(&graph).unwrap(), (4., vec![idx[1], idx[2]]));
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let (graph, idx) = build_weighted_graph(
+            5,
+            &[(0, 1, 1.), (0, 2, 4.), (1, 2, 2.), (1, 3, 6.), (2, 3, 1.)],
+        );
+        let costs = dijkstra(&graph, idx[0]);
+        assert_eq!(costs.len(), 4);
+        assert_eq!(costs[idx[0]], 0.);
+        assert_eq!(costs[idx[1]], 1.);
+        assert_eq!(costs[idx[2]], 3.);
+        assert_eq!(costs[idx[3]], 4.);
+        assert_eq!(
+            dijkstra_path(&graph, idx[0], idx[3]),
+            Some((4., vec![idx[0], idx[1], idx[2], idx[3]]))
+        );
+        assert_eq!(dijkstra_path(&graph, idx[0], idx[4]), None);
+        assert_eq!(
+            dijkstra_path(&graph, idx[2], idx[2]),
+            Some((0., vec![idx[2]]))
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_by() {
+        let arena = Bump::new();
+        // 0 -> 3 directly is one hop but costs more than 0 -> 1 -> 2 -> 3
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 3), (0, 1), (1, 2), (2, 3), (3, 4)]);
+        let weight = |parent, child, _: &_| {
+            if (parent, child) == (idx[0], idx[3]) {
+                10.
+            } else {
+                1.
+            }
+        };
+        assert_eq!(
+            shortest_path(&graph, idx[0], idx[3]),
+            Some(vec![idx[0], idx[3]])
+        );
+        assert_eq!(
+            dijkstra_path_by(&graph, idx[0], idx[3], weight),
+            Some((3., vec![idx[0], idx[1], idx[2], idx[3]]))
+        );
+        let costs = dijkstra_by(&graph, idx[0], weight);
+        assert_eq!(costs.len(), 5);
+        assert_eq!(costs[idx[4]], 4.);
+        assert_eq!(dijkstra_path_by(&graph, idx[4], idx[0], weight), None);
+
+        let negative = |_, child, _: &_| if child == idx[2] { -1. } else { 1. };
+        assert_eq!(
+            try_dijkstra_by(&graph, idx[0], negative),
+            Err(NegativeWeight {
+                parent: idx[1],
+                child: idx[2],
+                weight: -1.
+            })
+        );
+        assert_eq!(try_dijkstra_by(&graph, idx[2], negative).unwrap().len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "negative edge weight")]
+    fn test_dijkstra_negative_weight() {
+        let (graph, idx) = build_weighted_graph(2, &[(0, 1, -1.)]);
+        dijkstra(&graph, idx[0]);
+    }
+
+    #[test]
+    fn test_bellman_ford() {
+        let (graph, idx) =
+            build_weighted_graph(4, &[(0, 1, 4.), (0, 2, 1.), (2, 1, -2.), (1, 3, 1.)]);
+        let costs = bellman_ford(&graph, idx[0]).unwrap();
+        assert_eq!(costs[idx[1]], -1.);
+        assert_eq!(costs[idx[3]], 0.);
+    }
+
+    #[test]
+    fn test_bellman_ford_negative_cycle() {
+        let (graph, idx) = build_weighted_graph(
+            5,
+            &[(0, 1, 1.), (1, 2, 1.), (2, 3, -3.), (3, 1, 1.), (3, 4, 1.)],
+        );
+        let err = bellman_ford(&graph, idx[0]).unwrap_err();
+        assert!([idx[1], idx[2], idx[3]].contains(&err.node));
+        assert!(bellman_ford(&graph, idx[4]).is_ok());
+    }
+}
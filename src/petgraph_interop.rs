@@ -0,0 +1,103 @@
+use petgraph::graph::{DiGraph, NodeIndex};
+use slotmap::SecondaryMap;
+
+use crate::{Graph, MissingNodeError, Node, NodeArray, NodeIdx, SimpleNode};
+
+/// A petgraph copy of a graph with the index of every node
+type Converted<T> = (DiGraph<T, ()>, SecondaryMap<NodeIdx, NodeIndex>);
+
+/// Convert `graph` into a petgraph [`DiGraph`] with a clone of every node as its weight
+///
+/// Every edge is kept, parallel edges and self-loops included; the returned map translates each
+/// node to its petgraph index. A child that is not in the graph has no index, so it is an error
+pub fn to_petgraph<T: Node + Clone>(graph: &Graph<T>) -> Result<Converted<T>, MissingNodeError> {
+    let mut pet = DiGraph::with_capacity(graph.node_count(), graph.edge_count());
+    let mut mapping = SecondaryMap::new();
+    for (node, value) in graph.nodes() {
+        mapping.insert(node, pet.add_node(value.clone()));
+    }
+    for (node, child) in graph.edges() {
+        let Some(&target) = mapping.get(child) else {
+            return Err(MissingNodeError {
+                node: child,
+                parent: Some(node),
+            });
+        };
+        pet.add_edge(mapping[node], target, ());
+    }
+    Ok((pet, mapping))
+}
+
+/// Convert a petgraph [`DiGraph`] into a graph with a clone of every node weight as its payload
+///
+/// Edge weights are dropped and children are kept in edge order; the returned list translates
+/// each [`NodeIndex::index`] to the new key
+pub fn from_petgraph<N: Clone, E>(pet: &DiGraph<N, E>) -> (Graph<SimpleNode<N>>, Vec<NodeIdx>) {
+    let mut nodes = NodeArray::with_key();
+    let keys: Vec<NodeIdx> = pet
+        .node_weights()
+        .map(|weight| nodes.insert(SimpleNode::new(weight.clone())))
+        .collect();
+    for edge in pet.raw_edges() {
+        let child = keys[edge.target().index()];
+        nodes[keys[edge.source().index()]].children.push(child);
+    }
+    (Graph::new(nodes), keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GraphBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_petgraph_round_trip() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("a", 'a')
+            .add_node("b", 'b')
+            .add_node("c", 'c')
+            .add_edge("a", "b")
+            .add_edge("a", "b")
+            .add_edge("b", "b")
+            .add_edge("b", "c")
+            .add_edge("c", "a");
+        let (graph, names) = builder.build().unwrap();
+        let (pet, mapping) = to_petgraph(&graph).unwrap();
+        assert_eq!(pet.node_count(), 3);
+        assert_eq!(pet.edge_count(), 5);
+        let (a, b) = (mapping[names["a"]], mapping[names["b"]]);
+        assert_eq!(pet.edges_connecting(a, b).count(), 2);
+        assert_eq!(pet.edges_connecting(b, b).count(), 1);
+        assert_eq!(pet[a].data, 'a');
+
+        let (restored, keys) = from_petgraph(&pet);
+        assert_eq!(restored.nodes().len(), 3);
+        for (node, value) in graph.nodes() {
+            let restored = &restored.nodes()[keys[mapping[node].index()]];
+            assert_eq!(restored.data.data, value.data);
+            let children: Vec<NodeIdx> = value
+                .children
+                .iter()
+                .map(|&c| keys[mapping[c].index()])
+                .collect();
+            assert_eq!(restored.children, children);
+        }
+    }
+
+    #[test]
+    fn test_to_petgraph_dangling_child() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(SimpleNode::new('a'));
+        let b = graph.insert_child(a, SimpleNode::new('b'));
+        graph.nodes_mut().remove(b);
+        assert_eq!(
+            to_petgraph(&graph).unwrap_err(),
+            MissingNodeError {
+                node: b,
+                parent: Some(a)
+            }
+        );
+    }
+}
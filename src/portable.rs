@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{dense_ids, Graph, MissingNodeError, Node, NodeArray, NodeIdx, NodeMut, SimpleNode};
+
+/// A graph whose nodes refer to each other by position instead of by [`NodeIdx`], so it can be
+/// persisted and loaded in another process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableGraph<D> {
+    pub nodes: Vec<PortableNode<D>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableNode<D> {
+    pub data: D,
+    /// Positions of the children in [`PortableGraph::nodes`]
+    pub children: Vec<usize>,
+}
+
+/// A child position of a [`PortableGraph`] is past the end of its nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildOutOfRange {
+    pub node: usize,
+    pub child: usize,
+}
+impl fmt::Display for ChildOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node {} has child {} out of range",
+            self.node, self.child
+        )
+    }
+}
+impl std::error::Error for ChildOutOfRange {}
+
+/// Convert `graph` into a [`PortableGraph`] with the payload `data` produces for every node
+///
+/// Nodes are stored in iteration order. A child that is not in the graph has no position, so it
+/// is an error
+pub fn serialize_graph<T: Node, D>(
+    graph: &Graph<T>,
+    data: impl Fn(NodeIdx, &T) -> D,
+) -> Result<PortableGraph<D>, MissingNodeError> {
+    let positions = dense_ids(graph);
+    let mut nodes = Vec::with_capacity(positions.len());
+    for (i, node) in graph.nodes() {
+        let children = node
+            .children()
+            .iter()
+            .map(|&child| {
+                positions.get(child).copied().ok_or(MissingNodeError {
+                    node: child,
+                    parent: Some(i),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        nodes.push(PortableNode {
+            data: data(i, node),
+            children,
+        });
+    }
+    Ok(PortableGraph { nodes })
+}
+
+/// Rebuild a graph from `portable` with fresh keys
+///
+/// The returned list maps each position of `portable` to its new key
+pub fn deserialize_graph<D>(
+    portable: PortableGraph<D>,
+) -> Result<(Graph<SimpleNode<D>>, Vec<NodeIdx>), ChildOutOfRange> {
+    deserialize_graph_with(portable, SimpleNode::new)
+}
+
+/// Same as [`deserialize_graph`] but build every node from its payload with `make`
+///
+/// Any children `make` adds are kept before those of the portable node
+pub fn deserialize_graph_with<D, U: NodeMut>(
+    portable: PortableGraph<D>,
+    mut make: impl FnMut(D) -> U,
+) -> Result<(Graph<U>, Vec<NodeIdx>), ChildOutOfRange> {
+    let len = portable.nodes.len();
+    for (node, value) in portable.nodes.iter().enumerate() {
+        if let Some(&child) = value.children.iter().find(|&&child| child >= len) {
+            return Err(ChildOutOfRange { node, child });
+        }
+    }
+    let mut nodes = NodeArray::with_key();
+    let mut keys = Vec::with_capacity(len);
+    let mut children = Vec::with_capacity(len);
+    for value in portable.nodes {
+        keys.push(nodes.insert(make(value.data)));
+        children.push(value.children);
+    }
+    for (&key, children) in keys.iter().zip(children) {
+        let node = nodes.get_mut(key).unwrap();
+        for child in children {
+            node.push_child(keys[child]);
+        }
+    }
+    Ok((Graph::new(nodes), keys))
+}
+
+/// Serialized as a list of `(key, node)` pairs in iteration order
+impl<T: Serialize> Serialize for Graph<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.nodes())
+    }
+}
+
+/// Give every node a fresh key and rewrite the children to match
+///
+/// A duplicate key or a child that is not one of the keys is an error
+impl<'de, T: NodeMut + Deserialize<'de>> Deserialize<'de> for Graph<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = Vec::<(NodeIdx, T)>::deserialize(deserializer)?;
+        let mut nodes = NodeArray::with_key();
+        let mut remap = HashMap::with_capacity(entries.len());
+        for (old, value) in entries {
+            if remap.insert(old, nodes.insert(value)).is_some() {
+                return Err(de::Error::custom(format!("duplicate node key {old:?}")));
+            }
+        }
+        let mut missing = None;
+        for value in nodes.values_mut() {
+            value.retain_children(|child| {
+                match remap.get(child) {
+                    Some(&new) => *child = new,
+                    None => missing = Some(*child),
+                }
+                true
+            });
+        }
+        if let Some(child) = missing {
+            return Err(de::Error::custom(format!("child {child:?} is not a node")));
+        }
+        Ok(Graph::new(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{to_dot_opts, AdjNode, DotOptions, GraphBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_portable_round_trip() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("a", "a".to_string())
+            .add_node("b", "b".to_string())
+            .add_node("c", "c".to_string())
+            .add_node("lonely", "lonely".to_string())
+            .add_edge("a", "b")
+            .add_edge("a", "c")
+            .add_edge("c", "a")
+            .add_edge("b", "b");
+        let (graph, _) = builder.build().unwrap();
+        let portable = serialize_graph(&graph, |_, node| node.data.clone()).unwrap();
+        let json = serde_json::to_string(&portable).unwrap();
+        let parsed: PortableGraph<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, portable);
+
+        let (restored, keys) = deserialize_graph(parsed).unwrap();
+        assert_eq!(restored.nodes().len(), graph.nodes().len());
+        assert_eq!(
+            serialize_graph(&restored, |_, node| node.data.clone()).unwrap(),
+            portable
+        );
+        for ((_, node), &key) in graph.nodes().iter().zip(&keys) {
+            assert_eq!(restored.nodes()[key].data, node.data);
+        }
+    }
+
+    #[test]
+    fn test_serialize_graph_dangling_child() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node("a");
+        let b = graph.insert_child(a, "b");
+        graph.insert_child(b, "c");
+        graph.nodes_mut().remove(b);
+        let err = serialize_graph(&graph, |_, node| node.data).unwrap_err();
+        assert_eq!(
+            err,
+            MissingNodeError {
+                node: b,
+                parent: Some(a)
+            }
+        );
+    }
+
+    #[test]
+    fn test_portable_child_out_of_range() {
+        let json = r#"{"nodes":[{"data":null,"children":[1]},{"data":null,"children":[0,2]}]}"#;
+        let portable: PortableGraph<()> = serde_json::from_str(json).unwrap();
+        let err = deserialize_graph_with(portable, |()| AdjNode::default()).unwrap_err();
+        assert_eq!(err, ChildOutOfRange { node: 1, child: 2 });
+        assert_eq!(err.to_string(), "node 1 has child 2 out of range");
+    }
+
+    #[test]
+    fn test_graph_serde_round_trip() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("a", 1)
+            .add_node("b", 2)
+            .add_node("c", 3)
+            .add_edge("a", "b")
+            .add_edge("b", "c")
+            .add_edge("c", "a")
+            .add_edge("a", "a");
+        let (mut graph, names) = builder.build().unwrap();
+        graph.remove_node(names["b"]);
+        let d = graph.insert_child(names["c"], 4);
+        graph.add_edge(d, names["a"]).unwrap();
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let restored: Graph<SimpleNode<i32>> = serde_json::from_str(&json).unwrap();
+        let options = DotOptions {
+            stable_ids: true,
+            ..Default::default()
+        };
+        let dot = |graph: &Graph<SimpleNode<i32>>| {
+            to_dot_opts(graph, &options, |_, node| crate::DotNodeAttrs {
+                label: Some(node.data.to_string()),
+                ..Default::default()
+            })
+        };
+        assert_eq!(dot(&restored), dot(&graph));
+        assert_eq!(restored.edge_count(), graph.edge_count());
+    }
+
+    #[test]
+    fn test_graph_serde_unknown_child() {
+        let mut graph = Graph::new(NodeArray::with_key());
+        let a = graph.insert_node(());
+        let b = graph.insert_child(a, ());
+        graph.nodes_mut().remove(b);
+        let json = serde_json::to_string(&graph).unwrap();
+        let err = serde_json::from_str::<Graph<SimpleNode<()>>>(&json).unwrap_err();
+        assert!(err.to_string().contains("is not a node"));
+        let json =
+            serde_json::to_string(&[(a, AdjNode::default()), (a, AdjNode::default())]).unwrap();
+        let err = serde_json::from_str::<Graph<AdjNode>>(&json).unwrap_err();
+        assert!(err.to_string().contains("duplicate node key"));
+    }
+}
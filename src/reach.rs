@@ -0,0 +1,223 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use slotmap::SecondaryMap;
+
+use crate::{strongly_connected_components, topological_sort, Graph, Node, NodeIdx};
+
+/// Return every node reachable from `node` by following children
+///
+/// `node` itself is included only if it lies on a cycle
+pub fn descendants<T: Node>(graph: &Graph<T>, node: NodeIdx) -> SecondaryMap<NodeIdx, ()> {
+    reach(node, |n| graph.nodes().get(n).unwrap().children())
+}
+
+/// Return every node that reaches `node` by following children
+///
+/// `node` itself is included only if it lies on a cycle
+pub fn ancestors<T: Node>(graph: &Graph<T>, node: NodeIdx) -> SecondaryMap<NodeIdx, ()> {
+    let parents = graph.reverse_adjacency();
+    reach(node, |n| &parents[n])
+}
+
+/// Return whether a path leads from `from` to `to`, stopping as soon as `to` is found
+///
+/// A node always reaches itself
+pub fn is_reachable<T: Node>(graph: &Graph<T>, from: NodeIdx, to: NodeIdx) -> bool {
+    graph.dfs(from).any(|node| node == to)
+}
+
+/// Return the deepest node reachable from `roots` that reaches both `a` and `b`, a node reaching
+/// itself
+///
+/// The depth of a node is the length of the longest path to it from `roots`, so no descendant of
+/// the returned node also reaches both; ties go to the smallest [`NodeIdx`]
+///
+/// # Panics
+///
+/// Panics if the nodes reachable from `roots` contain a cycle
+pub fn lowest_common_ancestor<T: Node>(
+    graph: &Graph<T>,
+    roots: &[NodeIdx],
+    a: NodeIdx,
+    b: NodeIdx,
+) -> Option<NodeIdx> {
+    let order = topological_sort(graph, roots).unwrap_or_else(|e| panic!("{e}"));
+    let mut depth: SecondaryMap<NodeIdx, usize> = order.iter().map(|&node| (node, 0)).collect();
+    for &node in order.iter().rev() {
+        let next = depth[node] + 1;
+        for &child in graph.nodes().get(node).unwrap().children() {
+            depth[child] = depth[child].max(next);
+        }
+    }
+    let parents = graph.reverse_adjacency();
+    let mut of_a = reach(a, |n| &parents[n]);
+    of_a.insert(a, ());
+    let mut of_b = reach(b, |n| &parents[n]);
+    of_b.insert(b, ());
+    of_a.keys()
+        .filter(|&node| of_b.contains_key(node) && depth.contains_key(node))
+        .max_by_key(|&node| (depth[node], Reverse(node)))
+}
+
+/// Return the [`descendants`] of every node
+///
+/// Strongly connected components are processed in reverse topological order so each one reuses
+/// the sets already computed for the components it points to
+pub fn transitive_closure<T: Node>(graph: &Graph<T>) -> SecondaryMap<NodeIdx, HashSet<NodeIdx>> {
+    let mut closure: SecondaryMap<NodeIdx, HashSet<NodeIdx>> = SecondaryMap::new();
+    for component in strongly_connected_components(graph) {
+        let mut reached = HashSet::new();
+        for &member in &component {
+            for &child in graph.nodes().get(member).unwrap().children() {
+                reached.insert(child);
+                if let Some(below) = closure.get(child) {
+                    reached.extend(below);
+                }
+            }
+        }
+        for member in component {
+            closure.insert(member, reached.clone());
+        }
+    }
+    closure
+}
+
+impl<T: Node> Graph<T> {
+    /// Remove every node unreachable from `roots` and return how many were removed
+    ///
+    /// Surviving nodes only point to reachable nodes, so no edge is left dangling
+    pub fn collect_garbage(&mut self, roots: &[NodeIdx]) -> usize {
+        let roots: Vec<NodeIdx> = roots
+            .iter()
+            .copied()
+            .filter(|&root| self.nodes().contains_key(root))
+            .collect();
+        let marked: SecondaryMap<NodeIdx, ()> = self.dfs_multi(&roots).map(|n| (n, ())).collect();
+        let len = self.nodes().len();
+        self.nodes_mut().retain(|node, _| marked.contains_key(node));
+        len - self.nodes().len()
+    }
+}
+
+fn reach<'a>(node: NodeIdx, next: impl Fn(NodeIdx) -> &'a [NodeIdx]) -> SecondaryMap<NodeIdx, ()> {
+    let mut reached = SecondaryMap::new();
+    let mut stack = next(node).to_vec();
+    while let Some(node) = stack.pop() {
+        if reached.insert(node, ()).is_some() {
+            continue;
+        }
+        stack.extend(next(node).iter().filter(|&&n| !reached.contains_key(n)));
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::{build_graph, NodeA};
+
+    use super::*;
+
+    fn keys(set: SecondaryMap<NodeIdx, ()>) -> Vec<NodeIdx> {
+        let mut keys: Vec<NodeIdx> = set.keys().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_reach_dag() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3), (4, 2)]);
+        assert_eq!(keys(descendants(&graph, idx[0])), [idx[1], idx[2], idx[3]]);
+        assert_eq!(
+            keys(ancestors(&graph, idx[3])),
+            [idx[0], idx[1], idx[2], idx[4]]
+        );
+        assert!(keys(ancestors(&graph, idx[0])).is_empty());
+        assert!(is_reachable(&graph, idx[4], idx[3]));
+        assert!(!is_reachable(&graph, idx[4], idx[1]));
+        assert!(is_reachable(&graph, idx[1], idx[1]));
+    }
+
+    #[test]
+    fn test_reach_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 1), (2, 3)]);
+        assert_eq!(keys(descendants(&graph, idx[1])), [idx[1], idx[2], idx[3]]);
+        assert_eq!(keys(ancestors(&graph, idx[1])), [idx[0], idx[1], idx[2]]);
+        assert!(is_reachable(&graph, idx[2], idx[1]));
+        assert!(!is_reachable(&graph, idx[3], idx[0]));
+    }
+
+    #[test]
+    fn test_reach_self_loop() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 0), (0, 1), (2, 0)]);
+        assert_eq!(keys(descendants(&graph, idx[0])), [idx[0], idx[1]]);
+        assert_eq!(keys(ancestors(&graph, idx[0])), [idx[0], idx[2]]);
+        assert!(keys(descendants(&graph, idx[1])).is_empty());
+        assert_eq!(keys(ancestors(&graph, idx[1])), [idx[0], idx[2]]);
+    }
+
+    #[test]
+    fn test_lowest_common_ancestor() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 3 -> 4, 0 -> 2 -> 4, 0 -> 3, 3 -> 5, 6 -> 5
+        let edges = [
+            (0, 1),
+            (1, 3),
+            (3, 4),
+            (0, 2),
+            (2, 4),
+            (0, 3),
+            (3, 5),
+            (6, 5),
+        ];
+        let (graph, idx) = build_graph(&arena, 7, &edges);
+        let roots = [idx[0]];
+        let lca = |a: usize, b: usize| lowest_common_ancestor(&graph, &roots, idx[a], idx[b]);
+        assert_eq!(lca(4, 5), Some(idx[3]));
+        assert_eq!(lca(2, 3), Some(idx[0]));
+        assert_eq!(lca(3, 4), Some(idx[3]));
+        assert_eq!(lca(1, 1), Some(idx[1]));
+        assert_eq!(lca(5, 6), None);
+        assert_eq!(
+            lowest_common_ancestor(&graph, &[idx[0], idx[6]], idx[5], idx[6]),
+            Some(idx[6])
+        );
+    }
+
+    #[test]
+    fn test_transitive_closure() {
+        let arena = Bump::new();
+        let edges = [(0, 1), (1, 2), (2, 1), (2, 3), (4, 4), (4, 0), (5, 3)];
+        let (graph, idx) = build_graph(&arena, 7, &edges);
+        let closure = transitive_closure(&graph);
+        assert_eq!(closure.len(), 7);
+        for &node in &idx {
+            let mut reached: Vec<NodeIdx> = closure[node].iter().copied().collect();
+            reached.sort();
+            assert_eq!(reached, keys(descendants(&graph, node)));
+        }
+        assert!(closure[idx[4]].contains(&idx[4]));
+        assert!(closure[idx[6]].is_empty());
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 6, &[(0, 1), (0, 2), (2, 3), (3, 4), (4, 2)]);
+        assert_eq!(graph.collect_garbage(&[idx[0]]), 1);
+        assert!(!graph.nodes().contains_key(idx[5]));
+
+        *graph.nodes_mut().get_mut(idx[0]).unwrap() = NodeA {
+            children: bumpalo::vec![in &arena; idx[1]],
+        };
+        assert_eq!(graph.collect_garbage(&[idx[0]]), 3);
+        let mut left: Vec<NodeIdx> = graph.nodes().keys().collect();
+        left.sort();
+        assert_eq!(left, [idx[0], idx[1]]);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+}
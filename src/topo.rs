@@ -0,0 +1,355 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+};
+
+use slotmap::SecondaryMap;
+
+use crate::{Graph, Node, NodeIdx};
+
+/// The graph contains a cycle where an acyclic graph is required
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    /// The nodes forming the cycle in edge order
+    ///
+    /// The last node has an edge back to the first one
+    pub cycle: Vec<NodeIdx>,
+}
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph contains a cycle: {:?}", self.cycle)
+    }
+}
+impl std::error::Error for CycleError {}
+
+/// [`topological_sort_all`] could not order every node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologicalSortError {
+    /// A cycle among the unprocessed nodes, in the same form as [`CycleError::cycle`]
+    pub cycle: Vec<NodeIdx>,
+    /// Every node on a cycle or depending on one, in iteration order
+    pub unprocessed: Vec<NodeIdx>,
+}
+impl fmt::Display for TopologicalSortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes left unprocessed by a cycle: {:?}",
+            self.unprocessed.len(),
+            self.cycle
+        )
+    }
+}
+impl std::error::Error for TopologicalSortError {}
+impl From<TopologicalSortError> for CycleError {
+    fn from(e: TopologicalSortError) -> Self {
+        Self { cycle: e.cycle }
+    }
+}
+
+/// Return the nodes reachable from `starts` in topological order using Kahn's algorithm
+///
+/// Children come before their parents, the same as [`dependency_order`](crate::dependency_order)
+pub fn topological_sort<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<NodeIdx>, CycleError> {
+    let (reachable, parents) = reachable_parents(graph, starts);
+    Ok(kahn(graph, &reachable, &parents)?)
+}
+
+/// Return every node of the graph in topological order using Kahn's algorithm
+///
+/// Children come before their parents, the same as [`dependency_order`](crate::dependency_order)
+/// and the opposite of many other libraries. On a cycle, the error holds every node that could not
+/// be ordered, not just the ones on the cycle
+pub fn topological_sort_all<T: Node>(
+    graph: &Graph<T>,
+) -> Result<Vec<NodeIdx>, TopologicalSortError> {
+    let nodes: Vec<NodeIdx> = graph.nodes().keys().collect();
+    kahn(graph, &nodes, &graph.reverse_adjacency())
+}
+
+/// Same as [`dependency_order`](crate::dependency_order) but in a canonical order independent of
+/// the order of `starts` and of children
+///
+/// Among the nodes whose children are all visited, the smallest [`NodeIdx`] is visited first
+///
+/// # Panics
+///
+/// Panics if the nodes reachable from `starts` contain a cycle
+pub fn dependency_order_stable<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<NodeIdx> {
+    let (reachable, parents) = reachable_parents(graph, starts);
+    let mut pending_children = SecondaryMap::new();
+    let mut ready = BinaryHeap::new();
+    for &node in &reachable {
+        let pending = graph.nodes().get(node).unwrap().children().len();
+        pending_children.insert(node, pending);
+        if pending == 0 {
+            ready.push(Reverse(node));
+        }
+    }
+    let mut order = vec![];
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for &parent in &parents[node] {
+            let pending = pending_children.get_mut(parent).unwrap();
+            *pending -= 1;
+            if *pending == 0 {
+                ready.push(Reverse(parent));
+            }
+        }
+    }
+    if order.len() != reachable.len() {
+        panic!("{}", remaining_cycle(graph, &pending_children));
+    }
+    order
+}
+
+/// Group the nodes reachable from `starts` into layers where every node only depends on nodes of
+/// earlier layers
+///
+/// Layer 0 holds the nodes without children; the concatenation of the layers is a valid
+/// [`dependency_order`](crate::dependency_order)
+pub fn dependency_layers<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<Vec<NodeIdx>>, CycleError> {
+    let (reachable, parents) = reachable_parents(graph, starts);
+    let mut pending_children = SecondaryMap::new();
+    let mut layer = vec![];
+    for &node in &reachable {
+        let pending = graph.nodes().get(node).unwrap().children().len();
+        pending_children.insert(node, pending);
+        if pending == 0 {
+            layer.push(node);
+        }
+    }
+    let mut layers = vec![];
+    let mut visited = 0;
+    while !layer.is_empty() {
+        let mut next = vec![];
+        for &node in &layer {
+            for &parent in &parents[node] {
+                let pending = pending_children.get_mut(parent).unwrap();
+                *pending -= 1;
+                if *pending == 0 {
+                    next.push(parent);
+                }
+            }
+        }
+        visited += layer.len();
+        layers.push(layer);
+        layer = next;
+    }
+    if visited == reachable.len() {
+        return Ok(layers);
+    }
+    Err(remaining_cycle(graph, &pending_children))
+}
+
+/// Return the nodes reachable from `starts` in discovery order along with their parents among
+/// those nodes
+pub(crate) fn reachable_parents<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> (Vec<NodeIdx>, SecondaryMap<NodeIdx, Vec<NodeIdx>>) {
+    let mut parents: SecondaryMap<NodeIdx, Vec<NodeIdx>> = SecondaryMap::new();
+    let mut reachable = vec![];
+    let mut stack = vec![];
+    for &start in starts {
+        if parents.contains_key(start) {
+            continue;
+        }
+        parents.insert(start, vec![]);
+        reachable.push(start);
+        stack.push(start);
+    }
+    while let Some(node) = stack.pop() {
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if !parents.contains_key(child) {
+                parents.insert(child, vec![]);
+                reachable.push(child);
+                stack.push(child);
+            }
+            parents.get_mut(child).unwrap().push(node);
+        }
+    }
+    (reachable, parents)
+}
+
+/// Order `nodes`, which must be closed under children, leaves first
+fn kahn<T: Node>(
+    graph: &Graph<T>,
+    nodes: &[NodeIdx],
+    parents: &SecondaryMap<NodeIdx, Vec<NodeIdx>>,
+) -> Result<Vec<NodeIdx>, TopologicalSortError> {
+    let mut pending_children = SecondaryMap::new();
+    let mut queue = VecDeque::new();
+    for &node in nodes {
+        let pending = graph.nodes().get(node).unwrap().children().len();
+        pending_children.insert(node, pending);
+        if pending == 0 {
+            queue.push_back(node);
+        }
+    }
+    let mut order = vec![];
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for &parent in &parents[node] {
+            let pending = pending_children.get_mut(parent).unwrap();
+            *pending -= 1;
+            if *pending == 0 {
+                queue.push_back(parent);
+            }
+        }
+    }
+    if order.len() == nodes.len() {
+        return Ok(order);
+    }
+    let unprocessed = nodes
+        .iter()
+        .copied()
+        .filter(|&node| pending_children[node] != 0)
+        .collect();
+    let CycleError { cycle } = remaining_cycle(graph, &pending_children);
+    Err(TopologicalSortError { cycle, unprocessed })
+}
+
+/// Extract a cycle from the nodes Kahn's algorithm could not process
+///
+/// Every node with pending children has a child that also has pending children, so following
+/// them must eventually revisit a node
+pub(crate) fn remaining_cycle<T: Node>(
+    graph: &Graph<T>,
+    pending_children: &SecondaryMap<NodeIdx, usize>,
+) -> CycleError {
+    let is_pending = |node: NodeIdx| pending_children.get(node).is_some_and(|&n| n != 0);
+    let (mut node, _) = pending_children
+        .iter()
+        .find(|(_, &pending)| pending != 0)
+        .expect("no remaining nodes");
+    let mut position = SecondaryMap::new();
+    let mut path = vec![];
+    while !position.contains_key(node) {
+        position.insert(node, path.len());
+        path.push(node);
+        node = *graph
+            .nodes()
+            .get(node)
+            .unwrap()
+            .children()
+            .iter()
+            .find(|&&child| is_pending(child))
+            .unwrap();
+    }
+    let cycle = path.split_off(position[node]);
+    CycleError { cycle }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_topological_sort() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3), (4, 0)]);
+        let order = topological_sort(&graph, &[idx[0]]).unwrap();
+        assert_eq!(order.len(), 4);
+        let position = |i: usize| order.iter().position(|&n| n == idx[i]).unwrap();
+        assert!(position(3) < position(1));
+        assert!(position(3) < position(2));
+        assert!(position(1) < position(0));
+        assert!(position(2) < position(0));
+    }
+
+    #[test]
+    fn test_topological_sort_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 1), (2, 3)]);
+        let err = topological_sort(&graph, &[idx[0]]).unwrap_err();
+        let mut cycle = err.cycle;
+        cycle.sort();
+        let mut expected = vec![idx[1], idx[2]];
+        expected.sort();
+        assert_eq!(cycle, expected);
+    }
+
+    #[test]
+    fn test_topological_sort_all() {
+        let arena = Bump::new();
+        let edges = [(0, 1), (0, 2), (3, 2), (2, 4), (1, 4)];
+        let (graph, idx) = build_graph(&arena, 6, &edges);
+        let order = topological_sort_all(&graph).unwrap();
+        assert_eq!(order.len(), 6);
+        assert!(order.contains(&idx[5]));
+        let position = |i: usize| order.iter().position(|&n| n == idx[i]).unwrap();
+        for (parent, child) in edges {
+            assert!(position(child) < position(parent));
+        }
+    }
+
+    #[test]
+    fn test_topological_sort_all_cycle() {
+        let arena = Bump::new();
+        // 4 -> 2 <-> 3 -> 5, so 4 is stuck behind the cycle while 5 can be ordered
+        let edges = [(0, 1), (2, 3), (3, 2), (4, 2), (3, 5)];
+        let (graph, idx) = build_graph(&arena, 6, &edges);
+        let err = topological_sort_all(&graph).unwrap_err();
+        assert_eq!(err.cycle.len(), 2);
+        assert!(err.cycle.contains(&idx[2]));
+        assert!(err.cycle.contains(&idx[3]));
+        assert_eq!(err.unprocessed, [idx[2], idx[3], idx[4]]);
+        assert_eq!(CycleError::from(err.clone()).cycle, err.cycle);
+    }
+
+    #[test]
+    fn test_dependency_layers_diamond() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let mut layers = dependency_layers(&graph, &[idx[0]]).unwrap();
+        layers[1].sort();
+        let mut middle = vec![idx[1], idx[2]];
+        middle.sort();
+        assert_eq!(layers, [vec![idx[3]], middle, vec![idx[0]]]);
+    }
+
+    #[test]
+    fn test_dependency_layers_chains() {
+        let arena = Bump::new();
+        // 0 -> 1 -> 2 -> 3 and 4 -> 5
+        let (graph, idx) = build_graph(&arena, 6, &[(0, 1), (1, 2), (2, 3), (4, 5)]);
+        let mut layers = dependency_layers(&graph, &[idx[0], idx[4]]).unwrap();
+        for layer in &mut layers {
+            layer.sort();
+        }
+        assert_eq!(
+            layers,
+            [
+                vec![idx[3], idx[5]],
+                vec![idx[2], idx[4]],
+                vec![idx[1]],
+                vec![idx[0]],
+            ]
+        );
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (2, 1)]);
+        assert!(dependency_layers(&graph, &[idx[0]]).is_err());
+    }
+
+    #[test]
+    fn test_dependency_order_stable() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (0, 3), (1, 4), (2, 4)]);
+        let (shuffled, _) = build_graph(&arena, 5, &[(0, 3), (0, 2), (0, 1), (2, 4), (1, 4)]);
+        let order = dependency_order_stable(&graph, &[idx[0]]);
+        assert_eq!(order, [idx[3], idx[4], idx[1], idx[2], idx[0]]);
+        assert_eq!(dependency_order_stable(&shuffled, &[idx[0]]), order);
+        assert_eq!(dependency_order_stable(&graph, &[idx[2], idx[0]]), order);
+    }
+}
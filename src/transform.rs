@@ -0,0 +1,491 @@
+use slotmap::SecondaryMap;
+
+use crate::{dense_ids, AdjNode, Graph, MissingNodeError, Node, NodeArray, NodeIdx, NodeMut};
+
+/// Return a graph with every edge reversed
+///
+/// A slotmap cannot insert under a given key, so the transpose has its own keys; the returned map
+/// translates each original node to its counterpart in the transpose
+pub fn transpose<T: Node>(graph: &Graph<T>) -> (Graph<AdjNode>, SecondaryMap<NodeIdx, NodeIdx>) {
+    let mut nodes = NodeArray::with_key();
+    let mut mapping = SecondaryMap::new();
+    for node in graph.nodes().keys() {
+        mapping.insert(node, nodes.insert(AdjNode::default()));
+    }
+    for (parent, node) in graph.nodes() {
+        for &child in node.children() {
+            let child = mapping[child];
+            nodes.get_mut(child).unwrap().children.push(mapping[parent]);
+        }
+    }
+    (Graph::new(nodes), mapping)
+}
+
+/// Return the row-major `n * n` adjacency matrix of `graph` and the node of every row and column
+///
+/// Entry `row * n + col` is set if there is an edge from `order[row]` to `order[col]`, so rows
+/// are sources and columns are targets. Nodes are ordered by iteration order
+pub fn to_adjacency_matrix<T: Node>(graph: &Graph<T>) -> (Vec<bool>, Vec<NodeIdx>) {
+    let order: Vec<NodeIdx> = graph.nodes().keys().collect();
+    let index = dense_ids(graph);
+    let n = order.len();
+    let mut matrix = vec![false; n * n];
+    for (parent, child) in graph.edges() {
+        matrix[index[parent] * n + index[child]] = true;
+    }
+    (matrix, order)
+}
+
+/// Return the structure of `graph` restricted to `nodes`, keeping only the edges between them
+///
+/// The returned map translates each kept node to its counterpart in the new graph
+pub fn induced_subgraph<T: Node>(
+    graph: &Graph<T>,
+    nodes: &[NodeIdx],
+) -> (Graph<AdjNode>, SecondaryMap<NodeIdx, NodeIdx>) {
+    let mut new_nodes = NodeArray::with_key();
+    let mut mapping = SecondaryMap::new();
+    for &node in nodes {
+        if !mapping.contains_key(node) {
+            mapping.insert(node, new_nodes.insert(AdjNode::default()));
+        }
+    }
+    for (node, &new_node) in &mapping {
+        let children = graph.nodes().get(node).unwrap().children();
+        new_nodes[new_node].children = children
+            .iter()
+            .filter_map(|&child| mapping.get(child).copied())
+            .collect();
+    }
+    (Graph::new(new_nodes), mapping)
+}
+
+/// Same as [`induced_subgraph`] with the nodes reachable from `starts`
+///
+/// Unlike [`Graph::reachable_subgraph`] only the structure is kept and nothing is cloned
+pub fn reachable_subgraph<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> (Graph<AdjNode>, SecondaryMap<NodeIdx, NodeIdx>) {
+    let nodes: Vec<NodeIdx> = graph.dfs_multi(starts).collect();
+    induced_subgraph(graph, &nodes)
+}
+
+impl<T: Node> Graph<T> {
+    /// Return the parents of every node
+    ///
+    /// A parent appears once per edge it has into the node; nodes without parents map to an
+    /// empty list
+    pub fn reverse_adjacency(&self) -> SecondaryMap<NodeIdx, Vec<NodeIdx>> {
+        let mut parents: SecondaryMap<NodeIdx, Vec<NodeIdx>> = SecondaryMap::new();
+        for node in self.nodes().keys() {
+            parents.insert(node, vec![]);
+        }
+        for (parent, node) in self.nodes() {
+            for &child in node.children() {
+                parents[child].push(parent);
+            }
+        }
+        parents
+    }
+
+    /// Return the parents of `node`, scanning the whole graph
+    ///
+    /// Prefer [`Graph::reverse_adjacency`] or [`ReverseIndex`] for repeated queries
+    pub fn parents(&self, node: NodeIdx) -> Vec<NodeIdx> {
+        let mut parents = vec![];
+        for (parent, value) in self.nodes() {
+            for &child in value.children() {
+                if child == node {
+                    parents.push(parent);
+                }
+            }
+        }
+        parents
+    }
+}
+
+impl<T: Node> Graph<T> {
+    /// Return a graph with the same structure whose node payloads are produced by `f`
+    ///
+    /// Like [`transpose`], the new graph has fresh keys and the returned map translates to them.
+    /// Any children `f` puts in a node are replaced by the translated children of the original
+    pub fn map<U: NodeMut>(
+        &self,
+        mut f: impl FnMut(NodeIdx, &T) -> U,
+    ) -> (Graph<U>, SecondaryMap<NodeIdx, NodeIdx>) {
+        let mut nodes = NodeArray::with_key();
+        let mut mapping = SecondaryMap::new();
+        for (node, value) in self.nodes() {
+            let mut new_value = f(node, value);
+            new_value.retain_children(|_| false);
+            mapping.insert(node, nodes.insert(new_value));
+        }
+        for (node, value) in self.nodes() {
+            let new_value = nodes.get_mut(mapping[node]).unwrap();
+            for &child in value.children() {
+                if let Some(&new_child) = mapping.get(child) {
+                    new_value.push_child(new_child);
+                }
+            }
+        }
+        (Graph::new(nodes), mapping)
+    }
+}
+
+impl<T: NodeMut> Graph<T> {
+    /// Move every node of `other` into this graph
+    ///
+    /// The returned map translates the keys of `other` to their new keys in this graph
+    pub fn absorb(&mut self, other: Graph<T>) -> SecondaryMap<NodeIdx, NodeIdx> {
+        let mut mapping = SecondaryMap::new();
+        let mut absorbed = vec![];
+        for (node, value) in other.nodes {
+            let new_node = self.nodes.insert(value);
+            mapping.insert(node, new_node);
+            absorbed.push(new_node);
+        }
+        for node in absorbed {
+            let value = self.nodes.get_mut(node).unwrap();
+            value.retain_children(|child| {
+                // Drop edges that were already dangling in `other`
+                let Some(&new_child) = mapping.get(*child) else {
+                    return false;
+                };
+                *child = new_child;
+                true
+            });
+        }
+        mapping
+    }
+
+    /// Same as [`Graph::absorb`] but also add an edge for every `(parent, child)` in `bridges`
+    /// where `parent` is in this graph and `child` is in `other`
+    ///
+    /// Nothing is changed if an endpoint of a bridge is missing
+    pub fn absorb_with_edges(
+        &mut self,
+        other: Graph<T>,
+        bridges: &[(NodeIdx, NodeIdx)],
+    ) -> Result<SecondaryMap<NodeIdx, NodeIdx>, MissingNodeError> {
+        for &(parent, child) in bridges {
+            if !self.nodes.contains_key(parent) {
+                return Err(MissingNodeError {
+                    node: parent,
+                    parent: None,
+                });
+            }
+            if !other.nodes.contains_key(child) {
+                return Err(MissingNodeError {
+                    node: child,
+                    parent: Some(parent),
+                });
+            }
+        }
+        let mapping = self.absorb(other);
+        for &(parent, child) in bridges {
+            self.nodes
+                .get_mut(parent)
+                .unwrap()
+                .push_child(mapping[child]);
+        }
+        Ok(mapping)
+    }
+}
+
+impl<T: NodeMut + Clone> Graph<T> {
+    /// Return a new graph with clones of `nodes` and only the edges among them
+    ///
+    /// The returned map translates the original keys of `nodes` to the keys in the new graph
+    pub fn subgraph(&self, nodes: &[NodeIdx]) -> (Graph<T>, SecondaryMap<NodeIdx, NodeIdx>) {
+        let mut new_nodes = NodeArray::with_key();
+        let mut mapping = SecondaryMap::new();
+        for &node in nodes {
+            if mapping.contains_key(node) {
+                continue;
+            }
+            let value = self.nodes().get(node).unwrap().clone();
+            mapping.insert(node, new_nodes.insert(value));
+        }
+        for value in new_nodes.values_mut() {
+            value.retain_children(|child| {
+                let Some(&new_child) = mapping.get(*child) else {
+                    return false;
+                };
+                *child = new_child;
+                true
+            });
+        }
+        (Graph::new(new_nodes), mapping)
+    }
+
+    /// Same as [`Graph::subgraph`] with the nodes reachable from `starts`
+    pub fn reachable_subgraph(
+        &self,
+        starts: &[NodeIdx],
+    ) -> (Graph<T>, SecondaryMap<NodeIdx, NodeIdx>) {
+        let nodes: Vec<NodeIdx> = self.dfs_multi(starts).collect();
+        self.subgraph(&nodes)
+    }
+}
+
+/// Predecessors of every node, built once and queried many times
+#[derive(Debug, Clone)]
+pub struct ReverseIndex {
+    predecessors: SecondaryMap<NodeIdx, Vec<NodeIdx>>,
+}
+impl ReverseIndex {
+    pub fn build<T: Node>(graph: &Graph<T>) -> Self {
+        Self {
+            predecessors: graph.reverse_adjacency(),
+        }
+    }
+
+    /// Return the nodes with an edge into `node`
+    ///
+    /// A parent appears once per edge it has into `node`
+    pub fn predecessors(&self, node: NodeIdx) -> &[NodeIdx] {
+        self.predecessors.get(node).map_or(&[], |p| p.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::{depth_first_search_post_order, tests::build_graph};
+
+    use super::*;
+
+    #[test]
+    fn test_to_adjacency_matrix() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (0, 2), (1, 2), (2, 2), (0, 1)]);
+        let (matrix, order) = to_adjacency_matrix(&graph);
+        assert_eq!(order, idx);
+        #[rustfmt::skip]
+        let expected = [
+            false, true, true,
+            false, false, true,
+            false, false, true,
+        ];
+        assert_eq!(matrix, expected);
+
+        let (matrix, order) = to_adjacency_matrix(&Graph::<AdjNode>::new(NodeArray::with_key()));
+        assert!(matrix.is_empty() && order.is_empty());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (0, 2), (1, 2), (2, 2)]);
+        let (transposed, mapping) = transpose(&graph);
+        let children = |i: usize| &transposed.nodes().get(mapping[idx[i]]).unwrap().children;
+        assert!(children(0).is_empty());
+        assert_eq!(children(1), &[mapping[idx[0]]]);
+        assert_eq!(
+            children(2),
+            &[mapping[idx[0]], mapping[idx[1]], mapping[idx[2]]]
+        );
+    }
+
+    #[test]
+    fn test_reverse_adjacency_dag() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)]);
+        let parents = graph.reverse_adjacency();
+        assert_eq!(parents.len(), 4);
+        assert!(parents[idx[0]].is_empty());
+        assert_eq!(parents[idx[1]], [idx[0]]);
+        assert_eq!(parents[idx[3]], [idx[0], idx[1], idx[2]]);
+        assert_eq!(graph.parents(idx[3]), parents[idx[3]]);
+    }
+
+    #[test]
+    fn test_reverse_adjacency_self_loop() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 2, &[(0, 0), (0, 1), (0, 1)]);
+        let parents = graph.reverse_adjacency();
+        assert_eq!(parents[idx[0]], [idx[0]]);
+        assert_eq!(parents[idx[1]], [idx[0], idx[0]]);
+        assert_eq!(graph.parents(idx[0]), [idx[0]]);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 2), (2, 3), (3, 0)]);
+        let (sub, mapping) = graph.subgraph(&[idx[0], idx[2], idx[3]]);
+        assert_eq!(sub.nodes().len(), 3);
+        assert!(!mapping.contains_key(idx[1]));
+        let children = |i: usize| sub.nodes().get(mapping[idx[i]]).unwrap().children();
+        assert_eq!(children(0), [mapping[idx[2]]]);
+        assert_eq!(children(2), [mapping[idx[3]]]);
+        assert_eq!(children(3), [mapping[idx[0]]]);
+    }
+
+    #[test]
+    fn test_induced_subgraph() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 2), (2, 3), (3, 0)]);
+        let (sub, mapping) = induced_subgraph(&graph, &[idx[0], idx[2], idx[3], idx[0]]);
+        assert_eq!(sub.nodes().len(), 3);
+        assert_eq!(sub.validate(), Ok(()));
+        assert!(!mapping.contains_key(idx[1]));
+        let children = |i: usize| &sub.nodes()[mapping[idx[i]]].children;
+        assert_eq!(children(0), &[mapping[idx[2]]]);
+        assert_eq!(children(2), &[mapping[idx[3]]]);
+        assert_eq!(children(3), &[mapping[idx[0]]]);
+    }
+
+    #[test]
+    fn test_reachable_subgraph() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (3, 1), (3, 4)]);
+        let (sub, mapping) = graph.reachable_subgraph(&[idx[0]]);
+        assert_eq!(sub.nodes().len(), 3);
+        assert_eq!(sub.validate(), Ok(()));
+        assert!(!mapping.contains_key(idx[3]));
+        let children = |i: usize| sub.nodes().get(mapping[idx[i]]).unwrap().children();
+        assert_eq!(children(0), [mapping[idx[1]]]);
+        assert_eq!(children(1), [mapping[idx[2]]]);
+    }
+
+    #[test]
+    fn test_reachable_subgraph_adj() {
+        let arena = Bump::new();
+        let edges = [(0, 1), (1, 2), (2, 0), (3, 1), (3, 4), (5, 3)];
+        let (graph, idx) = build_graph(&arena, 6, &edges);
+        let (sub, mapping) = reachable_subgraph(&graph, &[idx[0], idx[4]]);
+        assert_eq!(sub.nodes().len(), 4);
+        assert!(!mapping.contains_key(idx[3]));
+        assert!(!mapping.contains_key(idx[5]));
+        let children = |i: usize| &sub.nodes()[mapping[idx[i]]].children;
+        assert_eq!(children(0), &[mapping[idx[1]]]);
+        assert_eq!(children(2), &[mapping[idx[0]]]);
+        assert!(children(4).is_empty());
+    }
+
+    #[test]
+    fn test_absorb() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 2, &[(0, 1)]);
+        let (other, other_idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (2, 0)]);
+        let mapping = graph
+            .absorb_with_edges(other, &[(idx[1], other_idx[0])])
+            .unwrap();
+        assert_eq!(graph.nodes().len(), 5);
+        assert_eq!(graph.validate(), Ok(()));
+        let children = |i: usize| graph.nodes().get(mapping[other_idx[i]]).unwrap().children();
+        assert_eq!(children(0), [mapping[other_idx[1]]]);
+        assert_eq!(children(2), [mapping[other_idx[0]]]);
+        assert_eq!(
+            graph.nodes().get(idx[1]).unwrap().children(),
+            [mapping[other_idx[0]]]
+        );
+        assert_eq!(depth_first_search_post_order(&graph, &[idx[0]]).len(), 5);
+    }
+
+    #[test]
+    fn test_absorb_missing_bridge() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 2, &[(0, 1)]);
+        let (mut other, other_idx) = build_graph(&arena, 2, &[(0, 1)]);
+        other.nodes_mut().remove(other_idx[1]);
+        let err = graph
+            .absorb_with_edges(other, &[(idx[0], other_idx[1])])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MissingNodeError {
+                node: other_idx[1],
+                parent: Some(idx[0])
+            }
+        );
+        assert_eq!(graph.nodes().len(), 2);
+    }
+
+    #[test]
+    fn test_map() {
+        struct Named {
+            name: String,
+            children: Vec<NodeIdx>,
+        }
+        impl Node for Named {
+            fn children(&self) -> &[NodeIdx] {
+                &self.children
+            }
+        }
+        #[derive(Debug)]
+        struct Counted {
+            name_len: usize,
+            child_count: usize,
+            children: Vec<NodeIdx>,
+        }
+        impl Node for Counted {
+            fn children(&self) -> &[NodeIdx] {
+                &self.children
+            }
+        }
+        impl NodeMut for Counted {
+            fn push_child(&mut self, child: NodeIdx) {
+                self.children.push(child);
+            }
+            fn retain_children(&mut self, keep: impl FnMut(&mut NodeIdx) -> bool) {
+                self.children.retain_mut(keep);
+            }
+        }
+
+        let mut nodes = NodeArray::with_key();
+        let leaf = nodes.insert(Named {
+            name: "leaf".into(),
+            children: vec![],
+        });
+        let root = nodes.insert(Named {
+            name: "root node".into(),
+            children: vec![leaf, leaf],
+        });
+        nodes.get_mut(leaf).unwrap().children.push(root);
+        let graph = Graph::new(nodes);
+
+        let (mapped, mapping) = graph.map(|_, node| Counted {
+            name_len: node.name.len(),
+            child_count: node.children.len(),
+            children: vec![],
+        });
+        assert_eq!(mapped.nodes().len(), 2);
+        for (node, value) in graph.nodes() {
+            let new_value = mapped.nodes().get(mapping[node]).unwrap();
+            assert_eq!(new_value.name_len, value.name.len());
+            assert_eq!(new_value.child_count, new_value.children.len());
+            let expected: Vec<NodeIdx> = value.children.iter().map(|&c| mapping[c]).collect();
+            assert_eq!(new_value.children, expected);
+        }
+    }
+
+    #[test]
+    fn test_map_after_removal() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+        graph.remove_node(idx[1]);
+        let copy = graph.nodes()[idx[2]].clone();
+        graph.nodes_mut().insert(copy);
+        let (mapped, mapping) = graph.map(|_, _| AdjNode::default());
+        assert_eq!(mapped.nodes().len(), 4);
+        assert!(!mapping.contains_key(idx[1]));
+        assert_eq!(mapped.validate(), Ok(()));
+        for (node, value) in graph.nodes() {
+            let expected: Vec<NodeIdx> = value.children.iter().map(|&c| mapping[c]).collect();
+            assert_eq!(mapped.nodes()[mapping[node]].children, expected);
+        }
+    }
+
+    #[test]
+    fn test_reverse_index() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 2), (1, 2)]);
+        let index = ReverseIndex::build(&graph);
+        assert!(index.predecessors(idx[0]).is_empty());
+        assert_eq!(index.predecessors(idx[2]), [idx[0], idx[1]]);
+    }
+}
@@ -0,0 +1,753 @@
+use std::collections::VecDeque;
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    checked_children, Deadlock, Graph, MissingNodeError, NextMove, Node, NodeIdx, SearchError,
+    SearchSummary, VisitParams,
+};
+
+/// Return the visited nodes in post-order
+///
+/// A node is visited exactly once; edges to already visited nodes are skipped so cycles terminate
+pub fn depth_first_search_post_order<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Vec<NodeIdx> {
+    try_depth_first_search_post_order(graph, starts).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`depth_first_search_post_order`] but return an error instead of panicking on a missing
+/// node
+pub fn try_depth_first_search_post_order<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Result<Vec<NodeIdx>, MissingNodeError> {
+    let mut visited = SecondaryMap::new();
+    // Children are checked once when their node is pushed
+    let mut stack: Vec<(NodeIdx, &[NodeIdx], usize)> = vec![];
+    let mut visit = vec![];
+    for &start in starts {
+        if visited.contains_key(start) {
+            continue;
+        }
+        visited.insert(start, ());
+        stack.push((start, checked_children(graph, start)?, 0));
+        while let Some((node, children, next_child)) = stack.last_mut() {
+            let node = *node;
+            let Some(&child) = children.get(*next_child) else {
+                stack.pop();
+                visit.push(node);
+                continue;
+            };
+            *next_child += 1;
+            if visited.contains_key(child) {
+                continue;
+            }
+            visited.insert(child, ());
+            stack.push((child, checked_children(graph, child)?, 0));
+        }
+    }
+    Ok(visit)
+}
+
+/// Return the visited nodes in reverse post-order
+///
+/// Every node comes before its descendants except across back edges, which makes this the usual
+/// iteration order for forward dataflow
+pub fn reverse_post_order<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<NodeIdx> {
+    let mut order = depth_first_search_post_order(graph, starts);
+    order.reverse();
+    order
+}
+
+/// Map each node in `order` to its position
+///
+/// If a node appears more than once, its first position wins
+pub fn order_index(order: &[NodeIdx]) -> SecondaryMap<NodeIdx, usize> {
+    let mut index = SecondaryMap::new();
+    for (i, &node) in order.iter().enumerate() {
+        index.entry(node).unwrap().or_insert(i);
+    }
+    index
+}
+
+/// Same as [`breadth_first_search`](crate::breadth_first_search) but in depth-first order
+///
+/// [`NextMove::Postpone`] puts the node back under its siblings so they are visited first
+///
+/// # Panics
+///
+/// Panics on a missing node or when every pending sibling is postponed in a row; see
+/// [`try_depth_first_search_with`]
+pub fn depth_first_search_with<T: Node>(
+    graph: &mut Graph<T>,
+    start: NodeIdx,
+    visit: &mut impl FnMut(VisitParams<'_, T>) -> NextMove,
+) -> SearchSummary {
+    try_depth_first_search_with(graph, start, visit).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as [`depth_first_search_with`] but return an error instead of panicking
+///
+/// Once as many visits in a row as there are pending siblings answer [`NextMove::Postpone`], each
+/// of them is waiting on another one and the traversal stops with [`SearchError::Deadlock`]
+pub fn try_depth_first_search_with<T: Node>(
+    graph: &mut Graph<T>,
+    start: NodeIdx,
+    visit: &mut impl FnMut(VisitParams<'_, T>) -> NextMove,
+) -> Result<SearchSummary, SearchError> {
+    let mut summary = SearchSummary {
+        visited: 0,
+        stopped: false,
+    };
+    struct Frame {
+        parent: Option<NodeIdx>,
+        depth: usize,
+        /// Pending children of `parent`
+        siblings: Vec<NodeIdx>,
+        postponed_in_row: usize,
+    }
+    let mut in_stack = SecondaryMap::new();
+    let mut stack = vec![Frame {
+        parent: None,
+        depth: 0,
+        siblings: vec![start],
+        postponed_in_row: 0,
+    }];
+    in_stack.insert(start, ());
+    while let Some(frame) = stack.last_mut() {
+        let Some(node) = frame.siblings.pop() else {
+            stack.pop();
+            continue;
+        };
+        let (parent, depth) = (frame.parent, frame.depth);
+        in_stack.remove(node);
+        if !graph.nodes().contains_key(node) {
+            return Err(MissingNodeError { node, parent }.into());
+        }
+        let params = VisitParams {
+            graph,
+            node,
+            depth,
+            parent,
+        };
+        let next_move = visit(params);
+        match next_move {
+            NextMove::Postpone => {
+                let frame = stack.last_mut().unwrap();
+                frame.siblings.insert(0, node);
+                in_stack.insert(node, ());
+                frame.postponed_in_row += 1;
+                if frame.postponed_in_row == frame.siblings.len() {
+                    let queued = frame.siblings.iter().rev().copied().collect();
+                    return Err(Deadlock { queued }.into());
+                }
+                continue;
+            }
+            NextMove::TerminateBranch => {
+                summary.visited += 1;
+                stack.last_mut().unwrap().postponed_in_row = 0;
+                continue;
+            }
+            NextMove::VisitChildren => {
+                summary.visited += 1;
+                stack.last_mut().unwrap().postponed_in_row = 0;
+            }
+            NextMove::Stop => {
+                summary.visited += 1;
+                summary.stopped = true;
+                break;
+            }
+        }
+        let mut children = vec![];
+        for &child in checked_children(graph, node)? {
+            if in_stack.contains_key(child) {
+                continue;
+            }
+            children.push(child);
+            in_stack.insert(child, ());
+        }
+        stack.push(Frame {
+            parent: Some(node),
+            depth: depth + 1,
+            siblings: children,
+            postponed_in_row: 0,
+        });
+    }
+    Ok(summary)
+}
+
+/// Kind of an edge with respect to a depth-first search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// The edge discovered its child
+    Tree,
+    /// The child is an ancestor still being explored, which closes a cycle
+    Back,
+    /// The child is an already finished descendant
+    Forward,
+    /// The child is already finished and not a descendant
+    Cross,
+}
+
+/// Classify every edge reachable from `starts` by depth-first discovery and finish times
+///
+/// Edges are returned in the order the search examines them
+pub fn classify_edges<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+) -> Vec<(NodeIdx, NodeIdx, EdgeKind)> {
+    let mut edges = vec![];
+    walk(graph, starts, &mut |step| {
+        if let Step::Edge(parent, child, kind) = step {
+            edges.push((parent, child, kind));
+        }
+    });
+    edges
+}
+
+/// An event of [`depth_first_events`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DfsEvent {
+    /// The node is reached for the first time
+    Discover(NodeIdx),
+    /// Every descendant of the node is finished
+    Finish(NodeIdx),
+    /// The edge discovers its child, which is discovered right after
+    TreeEdge(NodeIdx, NodeIdx),
+    /// The child is an ancestor still being explored, which closes a cycle
+    BackEdge(NodeIdx, NodeIdx),
+    /// The child is already finished
+    CrossOrForwardEdge(NodeIdx, NodeIdx),
+}
+
+/// Run a depth-first search from each of `starts` in turn, reporting every node discovery and
+/// finish and every edge of the reached nodes to `on_event`
+///
+/// Each node is discovered once; [`classify_edges`] tells forward and cross edges apart
+pub fn depth_first_events<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    on_event: &mut impl FnMut(DfsEvent),
+) {
+    walk(graph, starts, &mut |step| {
+        on_event(match step {
+            Step::Discover(node) => DfsEvent::Discover(node),
+            Step::Finish(node) => DfsEvent::Finish(node),
+            Step::Edge(parent, child, EdgeKind::Tree) => DfsEvent::TreeEdge(parent, child),
+            Step::Edge(parent, child, EdgeKind::Back) => DfsEvent::BackEdge(parent, child),
+            Step::Edge(parent, child, EdgeKind::Forward | EdgeKind::Cross) => {
+                DfsEvent::CrossOrForwardEdge(parent, child)
+            }
+        })
+    });
+}
+
+enum Step {
+    Discover(NodeIdx),
+    Finish(NodeIdx),
+    Edge(NodeIdx, NodeIdx, EdgeKind),
+}
+
+/// Classify edges by the discovery and finish times of a depth-first search
+fn walk<T: Node>(graph: &Graph<T>, starts: &[NodeIdx], on_step: &mut impl FnMut(Step)) {
+    let mut discovered = SecondaryMap::new();
+    let mut finished = SecondaryMap::new();
+    let mut time = 0;
+    let mut stack: Vec<(NodeIdx, usize)> = vec![];
+    for &start in starts {
+        if discovered.contains_key(start) {
+            continue;
+        }
+        discovered.insert(start, time);
+        time += 1;
+        on_step(Step::Discover(start));
+        stack.push((start, 0));
+        while let Some((node, next_child)) = stack.last_mut() {
+            let node = *node;
+            let children = graph.nodes().get(node).unwrap().children();
+            let Some(&child) = children.get(*next_child) else {
+                stack.pop();
+                finished.insert(node, time);
+                time += 1;
+                on_step(Step::Finish(node));
+                continue;
+            };
+            *next_child += 1;
+            let kind = match discovered.get(child) {
+                None => EdgeKind::Tree,
+                Some(_) if !finished.contains_key(child) => EdgeKind::Back,
+                Some(&child_discovered) if discovered[node] < child_discovered => EdgeKind::Forward,
+                Some(_) => EdgeKind::Cross,
+            };
+            on_step(Step::Edge(node, child, kind));
+            if kind == EdgeKind::Tree {
+                discovered.insert(child, time);
+                time += 1;
+                on_step(Step::Discover(child));
+                stack.push((child, 0));
+            }
+        }
+    }
+}
+
+/// Same as [`depth_first_search`](crate::depth_first_search) but never descend more than
+/// `max_depth` edges away from a start
+///
+/// A `max_depth` of 0 returns just the starts
+pub fn depth_first_search_limited<T: Node>(
+    graph: &Graph<T>,
+    starts: &[NodeIdx],
+    max_depth: usize,
+) -> Vec<NodeIdx> {
+    let mut in_stack = SecondaryMap::new();
+    let mut stack = vec![];
+    for &start in starts {
+        stack.push((start, 0));
+        in_stack.insert(start, ());
+    }
+    let mut visit = vec![];
+    while let Some((node, depth)) = stack.pop() {
+        in_stack.remove(node);
+        visit.push(node);
+        if depth == max_depth {
+            continue;
+        }
+        for &child in graph.nodes().get(node).unwrap().children() {
+            if in_stack.contains_key(child) {
+                continue;
+            }
+            stack.push((child, depth + 1));
+            in_stack.insert(child, ());
+        }
+    }
+    visit
+}
+
+/// Lazy pre-order depth-first traversal
+///
+/// Unlike [`depth_first_search`](crate::depth_first_search), a node is yielded at most once
+#[derive(Debug, Clone)]
+pub struct Dfs<'a, T> {
+    graph: &'a Graph<T>,
+    stack: Vec<NodeIdx>,
+    visited: SecondaryMap<NodeIdx, ()>,
+}
+impl<'a, T: Node> Dfs<'a, T> {
+    pub fn new(graph: &'a Graph<T>, starts: &[NodeIdx]) -> Self {
+        Self {
+            graph,
+            stack: starts.to_vec(),
+            visited: SecondaryMap::new(),
+        }
+    }
+}
+impl<T: Node> Iterator for Dfs<'_, T> {
+    type Item = NodeIdx;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.stack.pop()?;
+            if self.visited.contains_key(node) {
+                continue;
+            }
+            self.visited.insert(node, ());
+            for &child in self.graph.nodes().get(node).unwrap().children() {
+                if self.visited.contains_key(child) {
+                    continue;
+                }
+                self.stack.push(child);
+            }
+            return Some(node);
+        }
+    }
+}
+
+/// Return the visited nodes in pre-order, each node at most once
+///
+/// Unlike [`depth_first_search`](crate::depth_first_search), a node reached again through another
+/// path is not repeated
+pub fn depth_first_search_unique<T: Node>(graph: &Graph<T>, starts: &[NodeIdx]) -> Vec<NodeIdx> {
+    Dfs::new(graph, starts).collect()
+}
+
+/// Lazy depth-first traversal returned by [`dfs_iter`]
+pub type DfsIter<'g, T> = Dfs<'g, T>;
+
+/// Return a lazy pre-order depth-first traversal from `starts`
+pub fn dfs_iter<'g, T: Node>(graph: &'g Graph<T>, starts: &[NodeIdx]) -> DfsIter<'g, T> {
+    Dfs::new(graph, starts)
+}
+
+/// Lazy breadth-first traversal
+///
+/// A node is yielded at most once
+#[derive(Debug, Clone)]
+pub struct Bfs<'a, T> {
+    graph: &'a Graph<T>,
+    queue: VecDeque<NodeIdx>,
+    discovered: SecondaryMap<NodeIdx, ()>,
+}
+impl<'a, T: Node> Bfs<'a, T> {
+    pub fn new(graph: &'a Graph<T>, starts: &[NodeIdx]) -> Self {
+        let mut queue = VecDeque::new();
+        let mut discovered = SecondaryMap::new();
+        for &start in starts {
+            if discovered.contains_key(start) {
+                continue;
+            }
+            queue.push_back(start);
+            discovered.insert(start, ());
+        }
+        Self {
+            graph,
+            queue,
+            discovered,
+        }
+    }
+}
+impl<T: Node> Iterator for Bfs<'_, T> {
+    type Item = NodeIdx;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        for &child in self.graph.nodes().get(node).unwrap().children() {
+            if self.discovered.contains_key(child) {
+                continue;
+            }
+            self.queue.push_back(child);
+            self.discovered.insert(child, ());
+        }
+        Some(node)
+    }
+}
+
+/// Lazy breadth-first traversal returned by [`bfs_iter`]
+pub type BfsIter<'g, T> = Bfs<'g, T>;
+
+/// Return a lazy breadth-first traversal from `start` yielding each reachable node once
+pub fn bfs_iter<T: Node>(graph: &Graph<T>, start: NodeIdx) -> BfsIter<'_, T> {
+    Bfs::new(graph, &[start])
+}
+
+impl<T: Node> Graph<T> {
+    pub fn dfs(&self, start: NodeIdx) -> Dfs<'_, T> {
+        Dfs::new(self, &[start])
+    }
+    pub fn dfs_multi(&self, starts: &[NodeIdx]) -> Dfs<'_, T> {
+        Dfs::new(self, starts)
+    }
+    pub fn bfs(&self, start: NodeIdx) -> Bfs<'_, T> {
+        Bfs::new(self, &[start])
+    }
+    pub fn bfs_multi(&self, starts: &[NodeIdx]) -> Bfs<'_, T> {
+        Bfs::new(self, starts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use bumpalo::Bump;
+
+    use crate::{depth_first_search, tests::build_graph, NodeArray};
+
+    use super::*;
+
+    #[test]
+    fn test_post_order_diamond() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let visit = depth_first_search_post_order(&graph, &[idx[0]]);
+        assert_eq!(visit, [idx[3], idx[1], idx[2], idx[0]]);
+    }
+
+    #[test]
+    fn test_dfs_with_terminate_branch() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 6, &[(0, 1), (0, 2), (1, 3), (3, 4), (2, 5)]);
+        let mut visited = vec![];
+        depth_first_search_with(&mut graph, idx[0], &mut |params| {
+            visited.push(params.node);
+            if params.node == idx[1] {
+                return NextMove::TerminateBranch;
+            }
+            NextMove::VisitChildren
+        });
+        assert_eq!(visited, [idx[0], idx[2], idx[5], idx[1]]);
+    }
+
+    #[test]
+    fn test_dfs_with_depth_and_parent() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (0, 3)]);
+        let mut visited = vec![];
+        depth_first_search_with(&mut graph, idx[0], &mut |params| {
+            visited.push((params.node, params.depth, params.parent));
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            visited,
+            [
+                (idx[0], 0, None),
+                (idx[3], 1, Some(idx[0])),
+                (idx[1], 1, Some(idx[0])),
+                (idx[2], 2, Some(idx[1])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dfs_with_postpone() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (0, 3)]);
+        let mut visited = vec![];
+        let mut postponed = false;
+        depth_first_search_with(&mut graph, idx[0], &mut |params| {
+            if params.node == idx[3] && !postponed {
+                postponed = true;
+                return NextMove::Postpone;
+            }
+            visited.push(params.node);
+            NextMove::VisitChildren
+        });
+        assert_eq!(visited, [idx[0], idx[2], idx[1], idx[3]]);
+    }
+
+    #[test]
+    fn test_dfs_with_deadlock() {
+        let arena = Bump::new();
+        // 1 and 2 each wait for the other to be visited
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3)]);
+        let mut visited = vec![];
+        let result = try_depth_first_search_with(&mut graph, idx[0], &mut |params| {
+            if params.node == idx[1] && !visited.contains(&idx[2])
+                || params.node == idx[2] && !visited.contains(&idx[1])
+            {
+                return NextMove::Postpone;
+            }
+            visited.push(params.node);
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            result,
+            Err(SearchError::Deadlock(Deadlock {
+                queued: vec![idx[2], idx[1]]
+            }))
+        );
+        assert_eq!(visited, [idx[0]]);
+
+        // A lone postponed node has nothing to wait on
+        let result = try_depth_first_search_with(&mut graph, idx[0], &mut |_| NextMove::Postpone);
+        assert_eq!(
+            result,
+            Err(SearchError::Deadlock(Deadlock {
+                queued: vec![idx[0]]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_dfs_with_stop() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (2, 3)]);
+        let summary = depth_first_search_with(&mut graph, idx[0], &mut |params| {
+            if params.node == idx[2] {
+                return NextMove::Stop;
+            }
+            NextMove::VisitChildren
+        });
+        assert_eq!(
+            summary,
+            SearchSummary {
+                visited: 2,
+                stopped: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_post_order_deep_chain() {
+        let arena = Bump::new();
+        let len = 100_000;
+        let mut edges: Vec<(usize, usize)> = (0..len - 1).map(|i| (i, i + 1)).collect();
+        // Shared child reachable from every other node
+        edges.extend((0..len - 1).step_by(2).map(|i| (i, len - 1)));
+        let (graph, idx) = build_graph(&arena, len, &edges);
+        let visit = depth_first_search_post_order(&graph, &[idx[0]]);
+        let expected: Vec<NodeIdx> = idx.iter().rev().copied().collect();
+        assert_eq!(visit, expected);
+    }
+
+    #[test]
+    fn test_classify_edges() {
+        let arena = Bump::new();
+        let (graph, idx) =
+            build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 0), (0, 2), (3, 1), (3, 4)]);
+        let edges = classify_edges(&graph, &[idx[0], idx[3]]);
+        assert_eq!(
+            edges,
+            [
+                (idx[0], idx[1], EdgeKind::Tree),
+                (idx[1], idx[2], EdgeKind::Tree),
+                (idx[2], idx[0], EdgeKind::Back),
+                (idx[0], idx[2], EdgeKind::Forward),
+                (idx[3], idx[1], EdgeKind::Cross),
+                (idx[3], idx[4], EdgeKind::Tree),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dfs_limited() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (1, 2), (2, 3), (0, 4)]);
+        assert_eq!(
+            depth_first_search_limited(&graph, &[idx[0], idx[2]], 0),
+            [idx[2], idx[0]]
+        );
+        assert_eq!(
+            depth_first_search_limited(&graph, &[idx[0]], 2),
+            [idx[0], idx[4], idx[1], idx[2]]
+        );
+        assert_eq!(
+            depth_first_search_limited(&graph, &[idx[0]], 10),
+            depth_first_search(&graph, &[idx[0]])
+        );
+    }
+
+    #[test]
+    fn test_post_order_cycle() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 3, &[(0, 1), (1, 2), (2, 0)]);
+        let visit = depth_first_search_post_order(&graph, &[idx[1], idx[0]]);
+        assert_eq!(visit, [idx[0], idx[2], idx[1]]);
+    }
+
+    #[test]
+    fn test_dfs_iter_unique() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let visit: Vec<NodeIdx> = graph.dfs(idx[0]).collect();
+        assert_eq!(visit, [idx[0], idx[2], idx[3], idx[1]]);
+    }
+
+    #[test]
+    fn test_depth_first_events() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (1, 2), (2, 0), (0, 2), (3, 2)]);
+        let mut events = vec![];
+        depth_first_events(&graph, &[idx[0], idx[3]], &mut |event| events.push(event));
+        use DfsEvent::*;
+        assert_eq!(
+            events,
+            [
+                Discover(idx[0]),
+                TreeEdge(idx[0], idx[1]),
+                Discover(idx[1]),
+                TreeEdge(idx[1], idx[2]),
+                Discover(idx[2]),
+                BackEdge(idx[2], idx[0]),
+                Finish(idx[2]),
+                Finish(idx[1]),
+                CrossOrForwardEdge(idx[0], idx[2]),
+                Finish(idx[0]),
+                Discover(idx[3]),
+                CrossOrForwardEdge(idx[3], idx[2]),
+                Finish(idx[3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse_post_order_loop() {
+        let arena = Bump::new();
+        // entry -> header <-> body, header -> exit -> ret
+        let edges = [(0, 1), (1, 2), (2, 1), (1, 3), (3, 4)];
+        let (graph, idx) = build_graph(&arena, 5, &edges);
+        let order = reverse_post_order(&graph, &[idx[0]]);
+        assert_eq!(order.len(), 5);
+        assert_eq!(order[0], idx[0]);
+        let index = order_index(&order);
+        for (parent, child) in edges {
+            let forward = index[idx[parent]] < index[idx[child]];
+            assert_eq!(forward, (parent, child) != (2, 1));
+        }
+    }
+
+    #[test]
+    fn test_depth_first_search_unique_diamond() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let unique = depth_first_search_unique(&graph, &[idx[0]]);
+        assert_eq!(unique.len(), 4);
+        assert_eq!(unique.iter().filter(|&&n| n == idx[3]).count(), 1);
+        let repeated = crate::depth_first_search(&graph, &[idx[0]]);
+        assert_eq!(repeated.iter().filter(|&&n| n == idx[3]).count(), 2);
+    }
+
+    #[test]
+    fn test_dfs_iter_short_circuit() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (2, 3), (1, 4)]);
+        let mut iter = dfs_iter(&graph, &[idx[0]]);
+        assert_eq!(iter.find(|&n| n == idx[3]), Some(idx[3]));
+        assert_eq!(iter.collect::<Vec<_>>(), [idx[1], idx[4]]);
+    }
+
+    #[test]
+    fn test_bfs_iter_levels() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)]);
+        let order: Vec<_> = bfs_iter(&graph, idx[0]).collect();
+        assert_eq!(order, [idx[0], idx[1], idx[2], idx[3], idx[4]]);
+        assert_eq!(bfs_iter(&graph, idx[0]).skip(1).step_by(2).count(), 2);
+    }
+
+    #[test]
+    fn test_bfs_iter_unique() {
+        let arena = Bump::new();
+        let (graph, idx) = build_graph(&arena, 5, &[(0, 1), (0, 2), (1, 3), (2, 3), (4, 0)]);
+        let visit: Vec<NodeIdx> = graph.bfs_multi(&[idx[0], idx[1]]).collect();
+        assert_eq!(visit, [idx[0], idx[1], idx[2], idx[3]]);
+    }
+
+    struct CountingNode<'c> {
+        children: Vec<NodeIdx>,
+        calls: &'c Cell<usize>,
+    }
+    impl Node for CountingNode<'_> {
+        fn children(&self) -> &[NodeIdx] {
+            self.calls.set(self.calls.get() + 1);
+            &self.children
+        }
+    }
+
+    #[test]
+    fn test_lazy_iter_take() {
+        let calls = Cell::new(0);
+        let mut nodes = NodeArray::with_key();
+        let idx: Vec<NodeIdx> = (0..10_000)
+            .map(|_| {
+                nodes.insert(CountingNode {
+                    children: vec![],
+                    calls: &calls,
+                })
+            })
+            .collect();
+        for i in 0..idx.len() - 2 {
+            let children = &mut nodes.get_mut(idx[i]).unwrap().children;
+            children.push(idx[i + 1]);
+            children.push(idx[i + 2]);
+        }
+        let graph = Graph::new(nodes);
+
+        assert_eq!(graph.dfs(idx[0]).take(3).count(), 3);
+        assert!(calls.get() <= 3);
+        calls.set(0);
+        assert_eq!(graph.bfs(idx[0]).take(3).count(), 3);
+        assert!(calls.get() <= 3);
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+use slotmap::SecondaryMap;
+
+use crate::{Graph, Node, NodeIdx};
+
+/// An edge breaking one of the rules checked by [`Graph::validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationError {
+    pub parent: NodeIdx,
+    pub child: NodeIdx,
+    pub kind: ValidationKind,
+}
+impl ValidationError {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ValidationKind::Dangling => write!(
+                f,
+                "edge from {:?} to missing node {:?}",
+                self.parent, self.child
+            ),
+            ValidationKind::SelfLoop => write!(f, "self-loop on {:?}", self.parent),
+            ValidationKind::Duplicate => write!(
+                f,
+                "duplicate edge from {:?} to {:?}",
+                self.parent, self.child
+            ),
+        }
+    }
+}
+impl std::error::Error for ValidationError {}
+
+/// The rule a [`ValidationError`] breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationKind {
+    /// The child is absent from the graph, so traversals would panic on it
+    Dangling,
+    /// The child is the parent itself
+    SelfLoop,
+    /// The parent already lists the child earlier
+    Duplicate,
+}
+impl ValidationKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            ValidationKind::Dangling => Severity::Error,
+            ValidationKind::SelfLoop | ValidationKind::Duplicate => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Legal but often unintended
+    Warning,
+    /// Breaks the traversals
+    Error,
+}
+
+impl<T: Node> Graph<T> {
+    /// Check every edge for dangling children, self-loops and duplicates
+    ///
+    /// Every offending edge is reported, once per occurrence and in edge order; filter by
+    /// [`ValidationError::severity`] to only keep the dangling children that break traversals
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        let mut seen = SecondaryMap::new();
+        for (parent, node) in self.nodes() {
+            seen.clear();
+            for &child in node.children() {
+                let kind = if !self.nodes().contains_key(child) {
+                    ValidationKind::Dangling
+                } else if seen.insert(child, ()).is_some() {
+                    ValidationKind::Duplicate
+                } else if child == parent {
+                    ValidationKind::SelfLoop
+                } else {
+                    continue;
+                };
+                errors.push(ValidationError {
+                    parent,
+                    child,
+                    kind,
+                });
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::tests::build_graph;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let arena = Bump::new();
+        let (mut graph, idx) = build_graph(&arena, 4, &[(0, 1), (0, 2), (1, 2), (2, 3)]);
+        assert_eq!(graph.validate(), Ok(()));
+        graph.nodes_mut().remove(idx[2]);
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                ValidationError {
+                    parent: idx[0],
+                    child: idx[2],
+                    kind: ValidationKind::Dangling,
+                },
+                ValidationError {
+                    parent: idx[1],
+                    child: idx[2],
+                    kind: ValidationKind::Dangling,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_warnings() {
+        let arena = Bump::new();
+        let edges = [(0, 0), (0, 1), (0, 1), (0, 0), (1, 2), (2, 3), (2, 3)];
+        let (mut graph, idx) = build_graph(&arena, 4, &edges);
+        graph.nodes_mut().remove(idx[3]);
+        let errors = graph.validate().unwrap_err();
+        let found: Vec<(NodeIdx, NodeIdx, ValidationKind)> =
+            errors.iter().map(|e| (e.parent, e.child, e.kind)).collect();
+        assert_eq!(
+            found,
+            [
+                (idx[0], idx[0], ValidationKind::SelfLoop),
+                (idx[0], idx[1], ValidationKind::Duplicate),
+                (idx[0], idx[0], ValidationKind::Duplicate),
+                (idx[2], idx[3], ValidationKind::Dangling),
+                (idx[2], idx[3], ValidationKind::Dangling),
+            ]
+        );
+        let hard = errors
+            .iter()
+            .filter(|e| e.severity() == Severity::Error)
+            .count();
+        assert_eq!(hard, 2);
+        assert_eq!(errors[0].to_string(), format!("self-loop on {:?}", idx[0]));
+    }
+}